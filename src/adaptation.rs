@@ -0,0 +1,209 @@
+extern crate rand;
+
+use rand::Rng;
+
+use crate::automata::Automata as Automata;
+use crate::mutation::{apply_mutations, mutate_with_operator, MutationOperator};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MutationSchedule {
+    // always allow up to max_mutations (the original behaviour)
+    Fixed,
+    // the 1/5th success rule: every `period` children, the limit
+    // is multiplied by `factor` if more than a fifth of them
+    // beat their parent, and divided by `factor` otherwise
+    OneFifth { period: usize, factor: f64 },
+    // the limit halves every `half_life` steps
+    ExponentialDecay { half_life: f64 },
+    // the limit drops in a straight line to zero over `steps` steps
+    LinearDecay { steps: usize },
+    // each automata carries its own mutation rate (chance of
+    // mutating each position), which is perturbed with a log
+    // normal step of size `learning_rate` before it is used
+    SelfAdaptive { learning_rate: f64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct MutationControl {
+    pub schedule: MutationSchedule,
+    // mutations applied to each child, one after the other
+    pub operators: Vec<MutationOperator>,
+    // the limit given in the configuration
+    pub initial: f64,
+    // the current maximum number of mutations
+    pub max_mutations: f64,
+    // number of steps seen so far
+    pub steps: usize,
+    // children that beat their parent out of all children
+    // since the last 1/5th rule update
+    pub successes: usize,
+    pub trials: usize,
+}
+
+impl MutationControl {
+    pub fn new(schedule: MutationSchedule, max_mutations: usize) -> MutationControl {
+        MutationControl {
+            schedule,
+            operators: vec![MutationOperator::Point],
+            initial: max_mutations as f64,
+            max_mutations: max_mutations as f64,
+            steps: 0,
+            successes: 0,
+            trials: 0,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.max_mutations.round() as usize
+    }
+
+    pub fn mutate(&self, automata: &mut Automata, locked: &[usize]) {
+        // mutate a child according to the schedule, with
+        // each of the operators in turn
+        if let MutationSchedule::SelfAdaptive { learning_rate } = self.schedule {
+            self_adaptive_mutate(automata, learning_rate, locked, &self.operators);
+            return;
+        }
+        let mut max_mutations = self.limit();
+        if self.schedule != MutationSchedule::Fixed {
+            // adaptive limits can grow past the rule string length
+            max_mutations = max_mutations.min(automata.rule_string.len());
+        }
+        for operator in &self.operators {
+            mutate_with_operator(automata, max_mutations, locked, *operator);
+        }
+    }
+
+    pub fn record(&mut self, success: bool) {
+        // note whether a child beat its parent
+        self.trials += 1;
+        if success {
+            self.successes += 1;
+        }
+        if let MutationSchedule::OneFifth { period, factor } = self.schedule {
+            assert!(1.0 <= factor);
+            if period <= self.trials {
+                if 5 * self.successes > self.trials {
+                    self.max_mutations *= factor;
+                } else {
+                    self.max_mutations /= factor;
+                }
+                self.successes = 0;
+                self.trials = 0;
+            }
+        }
+    }
+
+    pub fn step(&mut self) {
+        // called after every step (mating event or generation)
+        self.steps += 1;
+        match self.schedule {
+            MutationSchedule::ExponentialDecay { half_life } => {
+                self.max_mutations = self.initial * 0.5f64.powf(self.steps as f64 / half_life);
+            }
+            MutationSchedule::LinearDecay { steps } => {
+                let remaining = steps.saturating_sub(self.steps) as f64;
+                self.max_mutations = self.initial * remaining / steps.max(1) as f64;
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn self_adaptive_mutate(
+    automata: &mut Automata,
+    learning_rate: f64,
+    locked: &[usize],
+    operators: &[MutationOperator],
+) {
+    // first mutate the mutation rate, then use the new rate as
+    // the chance of mutating each position; the rate is kept
+    // between one position per rule string and one half
+    let mut rng = rand::thread_rng();
+    let length = automata.rule_string.len() as f64;
+    let rate = automata.mutation_rate * (learning_rate * standard_normal(&mut rng)).exp();
+    automata.mutation_rate = rate.max(1.0 / length).min(0.5);
+    let num_unlocked = (0..automata.rule_string.len()).filter(|i| !locked.contains(i)).count();
+    for operator in operators {
+        let num_mutations = (0..num_unlocked).filter(|_| rng.gen::<f64>() < automata.mutation_rate).count();
+        apply_mutations(automata, num_mutations, locked, *operator);
+    }
+}
+
+fn standard_normal<R: Rng>(rng: &mut R) -> f64 {
+    // box-muller transform
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_fifth_rule() {
+        let mut control = MutationControl::new(MutationSchedule::OneFifth { period: 5, factor: 2.0 }, 4);
+        // 2 out of 5 is more than a fifth
+        for success in &[true, true, false, false, false] {
+            control.record(*success);
+        }
+        assert_eq!(control.limit(), 8);
+        // 1 out of 5 is not
+        for success in &[true, false, false, false, false] {
+            control.record(*success);
+        }
+        assert_eq!(control.limit(), 4);
+        assert_eq!(control.trials, 0);
+    }
+
+    #[test]
+    fn decay_schedules() {
+        let mut control = MutationControl::new(MutationSchedule::ExponentialDecay { half_life: 2.0 }, 8);
+        control.step();
+        control.step();
+        assert_eq!(control.limit(), 4);
+        control.step();
+        control.step();
+        assert_eq!(control.limit(), 2);
+
+        let mut control = MutationControl::new(MutationSchedule::LinearDecay { steps: 4 }, 8);
+        control.step();
+        assert_eq!(control.limit(), 6);
+        for _ in 0..10 {
+            control.step();
+        }
+        assert_eq!(control.limit(), 0);
+    }
+
+    #[test]
+    fn fixed_limit() {
+        let mut control = MutationControl::new(MutationSchedule::Fixed, 3);
+        control.record(true);
+        control.step();
+        assert_eq!(control.limit(), 3);
+    }
+
+    #[test]
+    fn adaptive_limit_is_capped() {
+        // the limit is capped at the length of the rule string
+        let control = MutationControl::new(MutationSchedule::OneFifth { period: 5, factor: 2.0 }, 100);
+        let mut a1d = Automata::new1d(2, 1);
+        control.mutate(&mut a1d, &[0]);
+        assert_eq!(a1d.validate(), Ok(()));
+    }
+
+    #[test]
+    fn self_adaptive_rate() {
+        let mut a1d = Automata::new1d(4, 2);
+        a1d.mutation_rate = 0.1;
+        for _ in 0..100 {
+            self_adaptive_mutate(&mut a1d, 0.5, &[0], &[MutationOperator::Point]);
+            assert!(1.0 / 16.0 <= a1d.mutation_rate && a1d.mutation_rate <= 0.5);
+            assert_eq!(a1d.validate(), Ok(()));
+        }
+        // the rate is inherited by copies
+        let child = a1d.clone();
+        assert_eq!(child.mutation_rate, a1d.mutation_rate);
+    }
+}
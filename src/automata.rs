@@ -3,12 +3,66 @@ extern crate rand;
 use rand::seq::SliceRandom;
 use rand::Rng;
 
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// positions in the rule string that mutation and crossover
+// leave alone by default: a neighbourhood of dead cells
+// (sum of 0) must not produce live cells
+pub const QUIESCENT_RULES: [usize; 1] = [0];
+
+// source of unique automata ids
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+pub fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvariantError {
+    NoStates,
+    WrongLength { expected: usize, found: usize },
+    StateOutOfRange { position: usize, state: usize },
+    NotQuiescent { state: usize },
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvariantError::NoStates => write!(f, "automata must have at least one state"),
+            InvariantError::WrongLength { expected, found } => {
+                write!(f, "rule string should have length {} but has length {}", expected, found)
+            }
+            InvariantError::StateOutOfRange { position, state } => {
+                write!(f, "rule {} is {}, which is not a valid state", position, state)
+            }
+            InvariantError::NotQuiescent { state } => {
+                write!(f, "first rule is {} but dead cells cannot produce live cells", state)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvariantError {}
+
 #[derive(Debug, Clone)]
 pub struct Automata {
     pub rule_string: Vec<usize>,
     pub is_2d: bool,
     pub neighbourhood_size: u8,
     pub num_states: usize,
+    // chance of mutating each position, only used (and changed)
+    // by self-adaptive mutation
+    pub mutation_rate: f64,
+    // unique id (copies share the id of the original) and the
+    // ids of the parents this automata was bred from, empty
+    // for automata made at random
+    pub id: usize,
+    pub parents: Vec<usize>,
+    // where crossover switched between the parents and which
+    // positions mutation changed when this automata was bred
+    pub cut_points: Vec<usize>,
+    pub mutated_positions: Vec<usize>,
 }
 
 impl Automata {
@@ -26,6 +80,11 @@ impl Automata {
             is_2d: false,
             neighbourhood_size,
             num_states,
+            mutation_rate: 1.0 / rule_string_length as f64,
+            id: next_id(),
+            parents: Vec::new(),
+            cut_points: Vec::new(),
+            mutated_positions: Vec::new(),
         }
     }
 
@@ -39,8 +98,55 @@ impl Automata {
             is_2d: true,
             neighbourhood_size,
             num_states,
+            mutation_rate: 1.0 / rule_string_length as f64,
+            id: next_id(),
+            parents: Vec::new(),
+            cut_points: Vec::new(),
+            mutated_positions: Vec::new(),
         }
     }
+
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        // check the rule string could have been made by new1d
+        // or new2d (and kept valid by mutation and crossover)
+        if self.num_states == 0 {
+            return Err(InvariantError::NoStates);
+        }
+        let n_width = self.neighbourhood_size as usize * 2 + 1;
+        let n_cells = if self.is_2d { n_width * n_width } else { n_width };
+        let expected = (self.num_states - 1) * n_cells + 1;
+        if self.rule_string.len() != expected {
+            return Err(InvariantError::WrongLength {
+                expected,
+                found: self.rule_string.len(),
+            });
+        }
+        for (position, state) in self.rule_string.iter().enumerate() {
+            if self.num_states <= *state {
+                return Err(InvariantError::StateOutOfRange {
+                    position,
+                    state: *state,
+                });
+            }
+        }
+        if self.rule_string[0] != 0 {
+            return Err(InvariantError::NotQuiescent {
+                state: self.rule_string[0],
+            });
+        }
+        Ok(())
+    }
+
+    pub fn distance(&self, other: &Automata) -> usize {
+        // hamming distance: the number of positions where
+        // the two rule strings differ
+        assert_eq!(self.rule_string.len(), other.rule_string.len());
+        self.rule_string
+            .iter()
+            .zip(other.rule_string.iter())
+            .filter(|(x, y)| x != y)
+            .count()
+    }
 }
 
 fn make_rules(length: usize, num_states: usize) -> Vec<usize> {
@@ -76,6 +182,47 @@ mod tests {
         assert_eq!(a1d.rule_string, vec![0]);
     }
 
+    #[test]
+    fn valid_automata() {
+        assert_eq!(Automata::new1d(4, 2).validate(), Ok(()));
+        assert_eq!(Automata::new2d(3, 1).validate(), Ok(()));
+        let mut a1d = Automata::new1d(3, 1);
+        a1d.rule_string[0] = 2;
+        assert_eq!(a1d.validate(), Err(InvariantError::NotQuiescent { state: 2 }));
+        a1d.rule_string[0] = 0;
+        a1d.rule_string[3] = 3;
+        assert_eq!(
+            a1d.validate(),
+            Err(InvariantError::StateOutOfRange { position: 3, state: 3 })
+        );
+        a1d.rule_string.pop();
+        assert_eq!(
+            a1d.validate(),
+            Err(InvariantError::WrongLength { expected: 7, found: 6 })
+        );
+        a1d.num_states = 0;
+        assert_eq!(a1d.validate(), Err(InvariantError::NoStates));
+    }
+
+    #[test]
+    fn unique_ids() {
+        let a1d = Automata::new1d(3, 1);
+        let a2d = Automata::new2d(3, 1);
+        assert_ne!(a1d.id, a2d.id);
+        assert_eq!(a1d.clone().id, a1d.id);
+        assert!(a1d.parents.is_empty());
+    }
+
+    #[test]
+    fn hamming_distance() {
+        let mut a1d = Automata::new1d(3, 1);
+        let copy_a1d = a1d.clone();
+        assert_eq!(a1d.distance(&copy_a1d), 0);
+        a1d.rule_string[1] = (a1d.rule_string[1] + 1) % 3;
+        a1d.rule_string[4] = (a1d.rule_string[4] + 2) % 3;
+        assert_eq!(a1d.distance(&copy_a1d), 2);
+    }
+
     #[test]
     fn make1d_zero_neighbours() {
         let a1d = Automata::new1d(5, 0);
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use aca::experiment::{Config, Experiment};
+use aca::palette::Palette;
+use aca::terminal;
+
+const USAGE: &str = "usage: aca [options]
+
+options:
+    --1d                 evolve 1D automata (2D by default)
+    --mevs N             number of mating events
+    --population N       population size
+    --states N           number of cell states
+    --tournament N       tournament size
+    --neighbourhood N    neighbourhood size
+    --width N            board width
+    --height N           board height
+    --iters N            number of layers for 2D boards (1 for 1D boards)
+    --start S,S,...      start population
+    --cuts N             maximum number of crossover cuts
+    --mutations N        maximum number of mutations
+    --palette NAME       greyscale, viridis or hues
+    --live               replay the best automata one generation at a time
+    --delay MS           time between generations in live mode
+    --help               show this message";
+
+struct Options {
+    config: Config,
+    palette: Palette,
+    live: bool,
+    delay: Duration,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        config: Config::default(),
+        palette: Palette::Viridis,
+        live: false,
+        delay: Duration::from_millis(100),
+    };
+    let config = &mut options.config;
+    let mut num_iters = None;
+    let mut max_cuts = None;
+    let mut max_mutations = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--1d" => config.is_2d = false,
+            "--live" => options.live = true,
+            "--mevs" => config.num_mevs = number(arg, value()?)?,
+            "--population" => config.population_size = number(arg, value()?)?,
+            "--states" => config.num_states = number(arg, value()?)?,
+            "--tournament" => config.tournament_size = number(arg, value()?)?,
+            "--neighbourhood" => config.neighbourhood_size = number(arg, value()?)?,
+            "--width" => config.width = number(arg, value()?)?,
+            "--height" => config.height = number(arg, value()?)?,
+            "--iters" => num_iters = Some(number(arg, value()?)?),
+            "--cuts" => max_cuts = Some(number(arg, value()?)?),
+            "--mutations" => max_mutations = Some(number(arg, value()?)?),
+            "--delay" => options.delay = Duration::from_millis(number(arg, value()?)?),
+            "--start" => {
+                config.start_population = value()?
+                    .split(',')
+                    .map(|s| number(arg, s))
+                    .collect::<Result<Vec<usize>, String>>()?;
+            }
+            "--palette" => options.palette = value()?.parse()?,
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+    config.fit_defaults(num_iters, max_cuts, max_mutations);
+    config.validate()?;
+    Ok(options)
+}
+
+fn number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("{} expects a number, not '{}'", arg, value))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    let mut experiment = Experiment::new(options.config.clone());
+    experiment.run();
+    let best = experiment.best();
+    let colours = best.colours(&options.palette);
+    if options.live {
+        let config = &options.config;
+        let frames = best.history(&config.start_population, config.num_iters);
+        let mut stdout = std::io::stdout();
+        if let Err(error) = terminal::replay(&mut stdout, &frames, &colours, options.delay) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    } else {
+        print!("{}", best.as_ansi(&colours));
+    }
+    println!("{}", best.result());
+    println!("{}", experiment.statistics());
+}
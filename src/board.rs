@@ -1,31 +1,39 @@
 
-extern crate rand;
-
-use rand::seq::SliceRandom;
-use rand::Rng;
-
 use crate::automata::Automata as Automata;
+use crate::cache::FitnessCache;
 use crate::colour::Colour as Colour;
+use crate::image::Image as Image;
+use crate::palette::Palette as Palette;
+use crate::svg::{board_to_svg, SvgOptions};
+use crate::terminal;
 
-pub fn make_boards(mut population: Vec<Automata>, width: usize, height: usize, num_iters: usize, start_population: &[usize]) -> Vec<Board> {
+pub fn make_boards(
+    mut population: Vec<Automata>,
+    width: usize,
+    height: usize,
+    num_iters: usize,
+    start_population: &[usize],
+    cache: &mut FitnessCache,
+) -> Vec<Board> {
+    // repeated rule strings are only run once
     let mut boards = Vec::with_capacity(population.len());
     for _ in 0..population.len() {
-        let mut board = Board::initialise(width, height);
         let automata = population.pop().unwrap();
-        board.fill(automata, &start_population);
-        for _ in 0..num_iters {
-            board.next_board();
-            if !board.apoptotic {
-                break;
-            }
-        }
-        board.is_apoptotic();
-        boards.push(board);
+        boards.push(cache.evaluate(automata, width, height, start_population, num_iters));
     }
     boards
 }
 
-#[derive(Debug)]
+pub fn evaluate(automata: Automata, width: usize, height: usize, start_population: &[usize], num_iters: usize) -> Board {
+    // run an automata on a new board; every board is run this
+    // way, so a cached board is the same whichever way it was
+    // first made
+    let mut board = Board::initialise(width, height);
+    board.run(automata, start_population, num_iters);
+    board
+}
+
+#[derive(Debug, Clone)]
 pub struct Board {
     width: usize,
     height: usize,
@@ -33,6 +41,9 @@ pub struct Board {
     pub automata: Option<Automata>,
     pub apoptotic: bool,
     pub fitness: usize,
+    // number of live cells in each generation, starting
+    // with the generation made from the start population
+    pub live_counts: Vec<usize>,
 }
 
 impl Board {
@@ -47,14 +58,53 @@ impl Board {
             automata: None,
             apoptotic: true,
             fitness: 0,
+            live_counts: Vec::new(),
         }
     }
 
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
     pub fn run(&mut self, automata: Automata, start_population: &[usize], num_iters: usize) {
+        self.run_with(automata, start_population, num_iters, |_| {});
+    }
+
+    pub fn run_recorded(&mut self, automata: Automata, start_population: &[usize], num_iters: usize) -> Vec<Vec<Vec<usize>>> {
+        // same as run, but keeps a copy of the cell states after
+        // filling and after each call to next_board; for 2D boards
+        // these are the layers that are otherwise thrown away
+        let mut layers = Vec::new();
+        self.run_with(automata, start_population, num_iters, |board| layers.push(board.cell_states.clone()));
+        layers
+    }
+
+    pub fn history(&self, start_population: &[usize], num_iters: usize) -> Vec<Vec<Vec<usize>>> {
+        // the cell states one generation at a time, for replaying
+        // a finished board: a 1D board already holds its whole
+        // history, so it is revealed one row at a time, while a
+        // 2D board has to be run again to get its layers back
+        let automata = self.automata.clone().expect("Board is missing automata.");
+        if automata.is_2d {
+            let mut board = Board::initialise(self.width, self.height);
+            board.run_recorded(automata, start_population, num_iters)
+        } else {
+            (1..=self.cell_states.len()).map(|n| self.cell_states[..n].to_vec()).collect()
+        }
+    }
+
+    fn run_with<F: FnMut(&Board)>(&mut self, automata: Automata, start_population: &[usize], num_iters: usize, mut on_layer: F) {
         self.fill(automata, start_population);
+        on_layer(self);
         self.next_board();
+        on_layer(self);
         for _ in 0..num_iters - 1 {
             self.next_board();
+            on_layer(self);
             if !self.apoptotic {
                 break;
             }
@@ -91,6 +141,7 @@ impl Board {
             let padding_top = (self.height - start_width) / 2;
             let padding_left = (self.width - start_width) / 2;
 
+            let mut live = 0;
             for i in padding_top..padding_top + start_width {
                 for j in padding_left..padding_left + start_width {
                     let new_value =
@@ -98,9 +149,11 @@ impl Board {
                     self.cell_states[i][j] = new_value;
                     if new_value != 0 {
                         self.fitness += 1;
+                        live += 1;
                     }
                 }
             }
+            self.live_counts.push(live);
             // check if current layer hits wall
             for c in &self.cell_states[0] {
                 if *c != 0 {
@@ -128,13 +181,16 @@ impl Board {
             // is centred; excess padding will go to the right end
             let padding = (self.width - start_width) / 2;
             // fill in start population
+            let mut live = 0;
             for i in padding..padding + start_width {
                 let new_value = start_population[i - padding];
                 self.cell_states[0][i] = new_value;
                 if new_value != 0 {
                     self.fitness += 1;
+                    live += 1;
                 }
             }
+            self.live_counts.push(live);
             // see if first row already hit wall
             if self.cell_states[0][0] != 0 || self.cell_states[0][self.width - 1] != 0 {
                 self.apoptotic = false;
@@ -155,6 +211,7 @@ impl Board {
 
         if self.automata.as_ref().unwrap().is_2d {
             let prev_board = self.cell_states.clone();
+            let mut live = 0;
             for j in 0..self.height {
                 let start_row =
                     ((j + self.height) - automata.neighbourhood_size as usize) % self.height;
@@ -181,10 +238,12 @@ impl Board {
                     let new_state = automata.rule_string[neighbourhood_sum];
                     if new_state != 0 {
                         self.fitness += 1;
+                        live += 1;
                     }
                     self.cell_states[j][k] = new_state;
                 }
             }
+            self.live_counts.push(live);
             // check if current layer hits wall
             for c in &self.cell_states[0] {
                 if *c != 0 {
@@ -205,7 +264,11 @@ impl Board {
                 }
             }
         } else {
+            // every row after the first is worked out again, so
+            // their counts replace any from an earlier call
+            self.live_counts.truncate(1);
             for i in 1..self.height {
+                let mut live = 0;
                 for j in 0..self.width {
                     let left_index =
                         ((j + self.width) - automata.neighbourhood_size as usize) % self.width;
@@ -223,9 +286,11 @@ impl Board {
                     let new_state = automata.rule_string[neighbourhood_sum];
                     if new_state != 0 {
                         self.fitness += 1;
+                        live += 1;
                     }
                     self.cell_states[i][j] = new_state;
                 }
+                self.live_counts.push(live);
                 // check if wall was hit
                 if self.cell_states[i][0] != 0 || self.cell_states[i][self.width - 1] != 0 {
                     self.apoptotic = false;
@@ -271,23 +336,10 @@ impl Board {
         true
     }
 
-    fn generate_colours(&self) -> Vec<Colour> {
-        if self.automata.is_none() {
-            panic!("Missing automata");
-        }
-        // make random colours to match
-        // the number of states
-        let mut rng = rand::thread_rng();
-        let mut colours = Vec::with_capacity(self.automata.as_ref().unwrap().num_states);
-        for _ in 0..self.automata.as_ref().unwrap().num_states {
-            let r: u8 = rng.gen();
-            let g: u8 = rng.gen();
-            let b: u8 = rng.gen();
-            let a: f64 = rng.gen();
-            let c = Colour::new(r, g, b, a);
-            colours.push(c);
-        }
-        colours
+    pub fn colours(&self, palette: &Palette) -> Vec<Colour> {
+        // one colour for each state of the board's automata
+        let automata = self.automata.as_ref().expect("Missing automata");
+        palette.colours(automata.num_states)
     }
 
     pub fn as_html_table(&self, colours: &Vec<Colour>) -> String {
@@ -299,15 +351,39 @@ impl Board {
             for col in row {
                 contents.push_str("<td class='cell' style='background-color: ");
                 let c = colours.get(*col).expect("Not enough colours for all cell states");
-                contents.push_str(
-                    &format!("rgba({}, {}, {}, {});'></td>", c.r, c.g, c.b, c.a)
-                );
+                contents.push_str(&format!("{};'></td>", c.to_css()));
             }
             contents.push_str("</tr>");
         }
         contents
     }
 
+    pub fn as_image(&self, colours: &[Colour], scale: usize) -> Image {
+        // each cell becomes a scale by scale square; a 1D board
+        // keeps every generation as a row, so its image is a
+        // space-time diagram (time going down), while a 2D board
+        // only has its current layer
+        Image::from_cells(&self.cell_states, colours, scale)
+    }
+
+    pub fn write_png(&self, output_file: &str, colours: &[Colour], scale: usize) -> std::io::Result<()> {
+        self.as_image(colours, scale).write_png(output_file)
+    }
+
+    pub fn as_svg(&self, colours: &[Colour], options: &SvgOptions) -> String {
+        board_to_svg(self, colours, options)
+    }
+
+    pub fn write_svg(&self, output_file: &str, colours: &[Colour], options: &SvgOptions) -> std::io::Result<()> {
+        std::fs::write(output_file, self.as_svg(colours, options))?;
+        Ok(())
+    }
+
+    pub fn as_ansi(&self, colours: &[Colour]) -> String {
+        // for viewing in a terminal, two rows of cells per line
+        terminal::render(&self.cell_states, colours)
+    }
+
     pub fn empty(&mut self) {
         // delete the current cell states, automata, and fitness
         // reset the apoptotic flag
@@ -315,6 +391,7 @@ impl Board {
         self.automata = None;
         self.apoptotic = true;
         self.fitness = 0;
+        self.live_counts = Vec::new();
     }
 
     pub fn result(&self) -> String {
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::automata::Automata as Automata;
+use crate::board::{evaluate, Board};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    // everything that decides the outcome of running a board
+    rule_string: Vec<usize>,
+    is_2d: bool,
+    neighbourhood_size: u8,
+    start_population: Vec<usize>,
+    width: usize,
+    height: usize,
+    num_iters: usize,
+}
+
+impl CacheKey {
+    pub fn new(automata: &Automata, width: usize, height: usize, start_population: &[usize], num_iters: usize) -> CacheKey {
+        CacheKey {
+            rule_string: automata.rule_string.clone(),
+            is_2d: automata.is_2d,
+            neighbourhood_size: automata.neighbourhood_size,
+            start_population: start_population.to_vec(),
+            width,
+            height,
+            num_iters,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FitnessCache {
+    // maximum number of boards kept (0 turns the cache off)
+    pub capacity: usize,
+    pub hits: usize,
+    pub misses: usize,
+    // number of boards actually run, which is every lookup
+    // that was not a hit (or every board if the cache is off)
+    pub evaluated: usize,
+    // boards with the time they were last used; the order map
+    // goes from that time back to the key, so the least recently
+    // used board is always the first entry
+    entries: HashMap<CacheKey, (Board, u64)>,
+    order: BTreeMap<u64, CacheKey>,
+    clock: u64,
+}
+
+impl FitnessCache {
+    pub fn new(capacity: usize) -> FitnessCache {
+        FitnessCache {
+            capacity,
+            hits: 0,
+            misses: 0,
+            evaluated: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / lookups as f64
+    }
+
+    pub fn get(&mut self, key: &CacheKey) -> Option<Board> {
+        // a copy of the cached board, which becomes the most
+        // recently used; counts as a hit or a miss unless the
+        // cache is off
+        if self.capacity == 0 {
+            return None;
+        }
+        self.clock += 1;
+        let clock = self.clock;
+        match self.entries.get_mut(key) {
+            Some((board, last_used)) => {
+                self.order.remove(last_used);
+                self.order.insert(clock, key.clone());
+                *last_used = clock;
+                self.hits += 1;
+                Some(board.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: CacheKey, board: Board) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        if let Some((_, last_used)) = self.entries.get(&key) {
+            self.order.remove(last_used);
+        } else if self.entries.len() == self.capacity {
+            // make room by dropping the least recently used board
+            let oldest = *self.order.keys().next().unwrap();
+            let oldest_key = self.order.remove(&oldest).unwrap();
+            self.entries.remove(&oldest_key);
+        }
+        self.order.insert(self.clock, key.clone());
+        self.entries.insert(key, (board, self.clock));
+    }
+
+    pub fn evaluate(
+        &mut self,
+        automata: Automata,
+        width: usize,
+        height: usize,
+        start_population: &[usize],
+        num_iters: usize,
+    ) -> Board {
+        // same as board::evaluate, but boards that have been run
+        // before are copied out of the cache; a cached copy is
+        // given the new automata, since other fields (e.g. the
+        // mutation rate) can differ between automata with the
+        // same rules
+        let key = CacheKey::new(&automata, width, height, start_population, num_iters);
+        if let Some(mut board) = self.get(&key) {
+            board.automata = Some(automata);
+            return board;
+        }
+        let board = evaluate(automata, width, height, start_population, num_iters);
+        self.evaluated += 1;
+        self.insert(key, board.clone());
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::make_boards;
+
+    fn automata(rule_string: Vec<usize>) -> Automata {
+        let mut a1d = Automata::new1d(3, 1);
+        a1d.rule_string = rule_string;
+        a1d
+    }
+
+    #[test]
+    fn hits_and_misses() {
+        let mut cache = FitnessCache::new(2);
+        let a = automata(vec![0, 0, 0, 0, 1, 0, 0]);
+        let first = cache.evaluate(a.clone(), 5, 3, &[1, 2, 1], 1);
+        let second = cache.evaluate(a.clone(), 5, 3, &[1, 2, 1], 1);
+        assert_eq!(first.cell_states, second.cell_states);
+        assert_eq!(first.fitness, second.fitness);
+        assert_eq!((cache.hits, cache.misses), (1, 1));
+        // a different start population is a different board
+        cache.evaluate(a, 5, 3, &[1], 1);
+        assert_eq!((cache.hits, cache.misses), (1, 2));
+        assert_eq!(cache.evaluated, 2);
+        assert_eq!(cache.hit_rate(), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn least_recently_used() {
+        let mut cache = FitnessCache::new(2);
+        let a = automata(vec![0, 1, 1, 1, 1, 1, 1]);
+        let b = automata(vec![0, 2, 2, 2, 2, 2, 2]);
+        let c = automata(vec![0, 1, 2, 1, 2, 1, 2]);
+        cache.evaluate(a.clone(), 5, 3, &[1], 1);
+        cache.evaluate(b.clone(), 5, 3, &[1], 1);
+        // using a makes b the least recently used
+        cache.evaluate(a.clone(), 5, 3, &[1], 1);
+        cache.evaluate(c, 5, 3, &[1], 1);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&CacheKey::new(&a, 5, 3, &[1], 1)).is_some());
+        assert!(cache.get(&CacheKey::new(&b, 5, 3, &[1], 1)).is_none());
+    }
+
+    #[test]
+    fn same_board_either_way() {
+        // a 2D board that hits the wall after one generation,
+        // made for the initial population and by evaluate
+        let mut a2d = Automata::new2d(2, 1);
+        a2d.rule_string = vec![0, 1, 1, 1, 1, 1, 1, 1, 1, 1];
+        let mut cache = FitnessCache::new(2);
+        let made = make_boards(vec![a2d.clone()], 5, 5, 4, &[1; 9], &mut cache).pop().unwrap();
+        let run = evaluate(a2d.clone(), 5, 5, &[1; 9], 4);
+        let cached = cache.evaluate(a2d, 5, 5, &[1; 9], 4);
+        assert_eq!(cache.hits, 1);
+        for board in &[run, cached] {
+            assert_eq!(made.cell_states, board.cell_states);
+            assert_eq!(made.live_counts, board.live_counts);
+            assert_eq!(made.fitness, board.fitness);
+        }
+    }
+
+    #[test]
+    fn turned_off() {
+        let mut cache = FitnessCache::new(0);
+        let a = automata(vec![0, 1, 1, 1, 1, 1, 1]);
+        cache.evaluate(a.clone(), 5, 3, &[1], 1);
+        cache.evaluate(a, 5, 3, &[1], 1);
+        assert!(cache.is_empty());
+        // lookups are not counted, but both boards were run
+        assert_eq!((cache.hits, cache.misses), (0, 0));
+        assert_eq!(cache.evaluated, 2);
+    }
+}
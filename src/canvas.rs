@@ -0,0 +1,122 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::colour::Colour as Colour;
+use crate::image::Image as Image;
+use crate::palette::Palette as Palette;
+use crate::wasm::{WasmBoard, WasmExperiment};
+
+pub fn draw_cells(
+    context: &CanvasRenderingContext2d,
+    cell_states: &[Vec<usize>],
+    colours: &[Colour],
+    scale: usize,
+) -> Result<(), JsValue> {
+    // the pixels are written straight into the canvas, which
+    // is much cheaper than building a table for every frame
+    let mut image = Image::from_cells(cell_states, colours, scale);
+    if image.width == 0 || image.height == 0 {
+        return Ok(());
+    }
+    let data = ImageData::new_with_u8_clamped_array_and_sh(
+        Clamped(&mut image.pixels[..]),
+        image.width as u32,
+        image.height as u32,
+    )?;
+    context.put_image_data(&data, 0.0, 0.0)
+}
+
+#[wasm_bindgen]
+pub struct CanvasRenderer {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    palette: Palette,
+    // width and height of each cell in pixels
+    scale: usize,
+    // the generations being replayed and the next one to draw
+    frames: Vec<Vec<Vec<usize>>>,
+    frame_colours: Vec<Colour>,
+    frame: usize,
+}
+
+#[wasm_bindgen]
+impl CanvasRenderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, scale: usize) -> Result<CanvasRenderer, JsValue> {
+        if scale == 0 {
+            return Err(JsValue::from_str("scale must be at least 1"));
+        }
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("the canvas has no 2d context"))?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        Ok(CanvasRenderer {
+            canvas,
+            context,
+            palette: Palette::Viridis,
+            scale,
+            frames: Vec::new(),
+            frame_colours: Vec::new(),
+            frame: 0,
+        })
+    }
+
+    #[wasm_bindgen(js_name = setPalette)]
+    pub fn set_palette(&mut self, name: &str) -> Result<(), JsValue> {
+        self.palette = name.parse().map_err(|message: String| JsValue::from_str(&message))?;
+        Ok(())
+    }
+
+    pub fn draw(&mut self, board: &WasmBoard) -> Result<(), JsValue> {
+        // draw the board as it is now; called once per
+        // generation this animates the evolution of the
+        // best automata
+        let board = board.board();
+        let colours = board.colours(&self.palette);
+        self.resize(board.width(), board.cell_states.len());
+        draw_cells(&self.context, &board.cell_states, &colours, self.scale)
+    }
+
+    pub fn replay(&mut self, experiment: &WasmExperiment, board: &WasmBoard) -> Result<(), JsValue> {
+        // get ready to draw the board one generation at a
+        // time with next_frame, starting from the first
+        let config = &experiment.experiment().config;
+        let board = board.board();
+        self.frames = board.history(&config.start_population, config.num_iters);
+        self.frame_colours = board.colours(&self.palette);
+        self.frame = 0;
+        // 1D replays grow a row at a time, so the canvas is
+        // sized for the whole board up front
+        self.resize(board.width(), board.cell_states.len());
+        self.context.clear_rect(0.0, 0.0, self.canvas.width() as f64, self.canvas.height() as f64);
+        self.next_frame().map(|_| ())
+    }
+
+    #[wasm_bindgen(js_name = nextFrame)]
+    pub fn next_frame(&mut self) -> Result<bool, JsValue> {
+        // draw the next generation of the replay; returns
+        // false once there is nothing left to draw
+        let frame = match self.frames.get(self.frame) {
+            Some(frame) => frame,
+            None => return Ok(false),
+        };
+        draw_cells(&self.context, frame, &self.frame_colours, self.scale)?;
+        self.frame += 1;
+        Ok(self.frame < self.frames.len())
+    }
+}
+
+impl CanvasRenderer {
+    fn resize(&self, columns: usize, rows: usize) {
+        // setting the size clears the canvas, so it is only
+        // done when the size actually changes
+        let width = (columns * self.scale) as u32;
+        let height = (rows * self.scale) as u32;
+        if self.canvas.width() != width || self.canvas.height() != height {
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+        }
+    }
+}
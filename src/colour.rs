@@ -1,5 +1,35 @@
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColourError {
+    // not hex, rgb(), rgba(), hsl() or hsla()
+    UnknownFormat(String),
+    // a component that could not be read as a number
+    InvalidComponent(String),
+    WrongComponentCount { expected: usize, found: usize },
+    // alpha, saturation or lightness outside 0 to 1
+    OutOfRange { component: &'static str, value: f64 },
+}
+
+impl fmt::Display for ColourError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColourError::UnknownFormat(s) => write!(f, "'{}' is not a hex, rgb or hsl colour", s),
+            ColourError::InvalidComponent(s) => write!(f, "'{}' is not a valid colour component", s),
+            ColourError::WrongComponentCount { expected, found } => {
+                write!(f, "expected {} colour components but found {}", expected, found)
+            }
+            ColourError::OutOfRange { component, value } => {
+                write!(f, "{} is {}, but should be between 0 and 1", component, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ColourError {}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Colour {
     // red, green, blue, opacity
     pub r: u8,
@@ -10,8 +40,172 @@ pub struct Colour {
 
 impl Colour {
     pub fn new(r: u8, g: u8, b: u8, a: f64) -> Colour {
-        assert!(0.0 <= a && a <= 1.0);
-        Colour { r, g, b, a }
+        // an alpha outside 0 to 1 is clamped (NaN becomes 0);
+        // use try_new to get an error for it instead
+        Colour { r, g, b, a: clamp_unit(a) }
+    }
+
+    pub fn try_new(r: u8, g: u8, b: u8, a: f64) -> Result<Colour, ColourError> {
+        // never panics: a bad alpha is an error
+        Ok(Colour { r, g, b, a: unit("alpha", a)? })
+    }
+
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64, a: f64) -> Result<Colour, ColourError> {
+        // hue in degrees, saturation and lightness between 0 and 1
+        let saturation = unit("saturation", saturation)?;
+        let lightness = unit("lightness", lightness)?;
+        if !hue.is_finite() {
+            return Err(ColourError::InvalidComponent(hue.to_string()));
+        }
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as usize {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = lightness - chroma / 2.0;
+        let byte = |v: f64| ((v + m) * 255.0).round() as u8;
+        Colour::try_new(byte(r), byte(g), byte(b), a)
+    }
+
+    pub fn to_hex(&self) -> String {
+        // #rrggbb, or #rrggbbaa if the colour is not opaque
+        let hex = format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b);
+        if self.a < 1.0 {
+            format!("{}{:02x}", hex, self.alpha_byte())
+        } else {
+            hex
+        }
+    }
+
+    pub fn to_css(&self) -> String {
+        format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+    }
+
+    pub fn interpolate(&self, other: &Colour, t: f64) -> Colour {
+        // t of 0 gives this colour and 1 gives the other;
+        // values outside 0 to 1 are clamped
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let mix = |x: u8, y: u8| (x as f64 + (y as f64 - x as f64) * t).round() as u8;
+        Colour {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+
+    pub fn to_rgba(&self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.alpha_byte()]
+    }
+
+    pub fn to_premultiplied_rgba(&self) -> [u8; 4] {
+        // red, green and blue scaled by the alpha, as canvas
+        // and most image compositing expect
+        let scale = |v: u8| (v as f64 * self.a).round() as u8;
+        [scale(self.r), scale(self.g), scale(self.b), self.alpha_byte()]
+    }
+
+    fn alpha_byte(&self) -> u8 {
+        (self.a * 255.0).round() as u8
+    }
+}
+
+impl fmt::Display for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl FromStr for Colour {
+    type Err = ColourError;
+
+    fn from_str(s: &str) -> Result<Colour, ColourError> {
+        // css style colours: #rrggbb, #rrggbbaa, rgb(r, g, b),
+        // rgba(r, g, b, a), hsl(h, s%, l%) and hsla(h, s%, l%, a)
+        let s = s.trim().to_lowercase();
+        if let Some(hex) = s.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        let open = s.find('(').ok_or_else(|| ColourError::UnknownFormat(s.clone()))?;
+        if !s.ends_with(')') {
+            return Err(ColourError::UnknownFormat(s.clone()));
+        }
+        let name = s[..open].trim();
+        let components: Vec<&str> = s[open + 1..s.len() - 1].split(',').map(|c| c.trim()).collect();
+        let expected = match name {
+            "rgb" | "hsl" => 3,
+            "rgba" | "hsla" => 4,
+            _ => return Err(ColourError::UnknownFormat(s.clone())),
+        };
+        if components.len() != expected {
+            return Err(ColourError::WrongComponentCount { expected, found: components.len() });
+        }
+        let a = if expected == 4 { parse_number(components[3])? } else { 1.0 };
+        if name.starts_with("rgb") {
+            Colour::try_new(
+                parse_byte(components[0])?,
+                parse_byte(components[1])?,
+                parse_byte(components[2])?,
+                a,
+            )
+        } else {
+            Colour::from_hsl(
+                parse_number(components[0])?,
+                parse_percentage(components[1])?,
+                parse_percentage(components[2])?,
+                a,
+            )
+        }
+    }
+}
+
+pub(crate) fn clamp_unit(value: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+fn unit(component: &'static str, value: f64) -> Result<f64, ColourError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(ColourError::OutOfRange { component, value })
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Colour, ColourError> {
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ColourError::UnknownFormat(format!("#{}", hex)));
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    let a = if hex.len() == 8 { byte(6) as f64 / 255.0 } else { 1.0 };
+    Colour::try_new(byte(0), byte(2), byte(4), a)
+}
+
+fn parse_number(s: &str) -> Result<f64, ColourError> {
+    match s.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(ColourError::InvalidComponent(s.to_string())),
+    }
+}
+
+fn parse_byte(s: &str) -> Result<u8, ColourError> {
+    s.parse::<u8>().map_err(|_| ColourError::InvalidComponent(s.to_string()))
+}
+
+fn parse_percentage(s: &str) -> Result<f64, ColourError> {
+    // "50%" or a fraction such as "0.5"
+    match s.strip_suffix('%') {
+        Some(percentage) => Ok(parse_number(percentage)? / 100.0),
+        None => parse_number(s),
     }
 }
 
@@ -34,4 +228,70 @@ mod tests {
         println!("{:?}", blue);
     }
 
+    #[test]
+    fn hsl_colours() {
+        assert_eq!(Colour::from_hsl(0.0, 1.0, 0.5, 1.0), Ok(Colour::new(255, 0, 0, 1.0)));
+        assert_eq!(Colour::from_hsl(120.0, 1.0, 0.5, 1.0), Ok(Colour::new(0, 255, 0, 1.0)));
+        assert_eq!(Colour::from_hsl(240.0, 1.0, 0.5, 1.0), Ok(Colour::new(0, 0, 255, 1.0)));
+        assert_eq!(Colour::from_hsl(-120.0, 1.0, 0.5, 1.0), Ok(Colour::new(0, 0, 255, 1.0)));
+        assert_eq!(Colour::from_hsl(200.0, 0.0, 1.0, 0.5), Ok(Colour::new(255, 255, 255, 0.5)));
+        assert_eq!(
+            Colour::from_hsl(0.0, 1.5, 0.5, 1.0),
+            Err(ColourError::OutOfRange { component: "saturation", value: 1.5 })
+        );
+    }
+
+    #[test]
+    fn parse_colours() {
+        let red = Colour::new(255, 0, 0, 1.0);
+        assert_eq!("#ff0000".parse(), Ok(red.clone()));
+        assert_eq!(" #FF0000 ".parse(), Ok(red.clone()));
+        assert_eq!("rgb(255, 0, 0)".parse(), Ok(red.clone()));
+        assert_eq!("hsl(0, 100%, 50%)".parse(), Ok(red.clone()));
+        assert_eq!("hsla(0, 1, 0.5, 1)".parse(), Ok(red));
+        assert_eq!("#00ff0080".parse(), Ok(Colour::new(0, 255, 0, 128.0 / 255.0)));
+        assert_eq!("rgba(0, 0, 255, 0.25)".parse(), Ok(Colour::new(0, 0, 255, 0.25)));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("red".parse::<Colour>(), Err(ColourError::UnknownFormat("red".to_string())));
+        assert_eq!("#ff00".parse::<Colour>(), Err(ColourError::UnknownFormat("#ff00".to_string())));
+        assert_eq!("#gg0000".parse::<Colour>(), Err(ColourError::UnknownFormat("#gg0000".to_string())));
+        assert_eq!(
+            "rgb(1, 2)".parse::<Colour>(),
+            Err(ColourError::WrongComponentCount { expected: 3, found: 2 })
+        );
+        assert_eq!("rgb(1, 2, 300)".parse::<Colour>(), Err(ColourError::InvalidComponent("300".to_string())));
+        assert_eq!(
+            "rgba(1, 2, 3, 2)".parse::<Colour>(),
+            Err(ColourError::OutOfRange { component: "alpha", value: 2.0 })
+        );
+        assert!(Colour::try_new(0, 0, 0, -0.5).is_err());
+        // the plain constructor clamps instead
+        assert_eq!(Colour::new(0, 0, 0, 1.5).a, 1.0);
+        assert_eq!(Colour::new(0, 0, 0, f64::NAN).a, 0.0);
+    }
+
+    #[test]
+    fn format_and_convert() {
+        let colour = Colour::new(255, 128, 0, 0.5);
+        assert_eq!(colour.to_hex(), "#ff800080");
+        assert_eq!(Colour::new(1, 2, 3, 1.0).to_hex(), "#010203");
+        assert_eq!(colour.to_css(), "rgba(255, 128, 0, 0.5)");
+        assert_eq!(colour.to_string().parse::<Colour>().unwrap().to_rgba(), colour.to_rgba());
+        assert_eq!(colour.to_rgba(), [255, 128, 0, 128]);
+        assert_eq!(colour.to_premultiplied_rgba(), [128, 64, 0, 128]);
+    }
+
+    #[test]
+    fn interpolation() {
+        let black = Colour::new(0, 0, 0, 0.0);
+        let white = Colour::new(255, 255, 255, 1.0);
+        assert_eq!(black.interpolate(&white, 0.0), black);
+        assert_eq!(black.interpolate(&white, 1.0), white);
+        assert_eq!(black.interpolate(&white, 0.5), Colour::new(128, 128, 128, 0.5));
+        assert_eq!(black.interpolate(&white, 7.0), white);
+    }
+
 }
\ No newline at end of file
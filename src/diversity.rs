@@ -0,0 +1,144 @@
+use crate::automata::Automata as Automata;
+use crate::board::Board as Board;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicatePolicy {
+    // children are evaluated even if an identical rule
+    // string is already in the population
+    Allow,
+    // duplicate children are thrown away without being run
+    Reject,
+    // duplicate children are mutated again up to `attempts`
+    // times, and thrown away if they are still duplicates
+    Remutate { attempts: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diversity {
+    // mean hamming distance over every pair of rule strings
+    pub mean_distance: f64,
+    // number of distinct rule strings
+    pub unique_rules: usize,
+    // shannon entropy (in bits) of the states at each position
+    // in the rule string
+    pub entropy: Vec<f64>,
+}
+
+impl Diversity {
+    pub fn mean_entropy(&self) -> f64 {
+        if self.entropy.is_empty() {
+            return 0.0;
+        }
+        self.entropy.iter().sum::<f64>() / self.entropy.len() as f64
+    }
+}
+
+pub fn diversity(population: &[Board]) -> Diversity {
+    // boards without automata are left out
+    let automata: Vec<&Automata> = population.iter().filter_map(|b| b.automata.as_ref()).collect();
+    Diversity {
+        mean_distance: mean_distance(&automata),
+        unique_rules: unique_rules(&automata),
+        entropy: entropy(&automata),
+    }
+}
+
+pub fn mean_distance(automata: &[&Automata]) -> f64 {
+    let mut total = 0;
+    let mut num_pairs = 0;
+    for i in 0..automata.len() {
+        for j in i + 1..automata.len() {
+            total += automata[i].distance(automata[j]);
+            num_pairs += 1;
+        }
+    }
+    if num_pairs == 0 {
+        return 0.0;
+    }
+    total as f64 / num_pairs as f64
+}
+
+pub fn unique_rules(automata: &[&Automata]) -> usize {
+    let mut rule_strings: Vec<&Vec<usize>> = automata.iter().map(|a| &a.rule_string).collect();
+    rule_strings.sort();
+    rule_strings.dedup();
+    rule_strings.len()
+}
+
+pub fn entropy(automata: &[&Automata]) -> Vec<f64> {
+    if automata.is_empty() {
+        return Vec::new();
+    }
+    let num_states = automata[0].num_states;
+    let length = automata[0].rule_string.len();
+    let mut entropy = Vec::with_capacity(length);
+    for position in 0..length {
+        let mut counts = vec![0; num_states];
+        for a in automata {
+            counts[a.rule_string[position]] += 1;
+        }
+        let mut h = 0.0;
+        for count in counts {
+            if count != 0 {
+                let p = count as f64 / automata.len() as f64;
+                h -= p * p.log2();
+            }
+        }
+        entropy.push(h);
+    }
+    entropy
+}
+
+pub fn is_duplicate(automata: &Automata, population: &[Board]) -> bool {
+    // true if a board in the population has the same rule string
+    population
+        .iter()
+        .filter_map(|b| b.automata.as_ref())
+        .any(|a| a.rule_string == automata.rule_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn automata(rule_string: Vec<usize>) -> Automata {
+        let mut a1d = Automata::new1d(3, 0);
+        a1d.rule_string = rule_string;
+        a1d
+    }
+
+    #[test]
+    fn population_diversity() {
+        let a = automata(vec![0, 1, 2]);
+        let b = automata(vec![0, 1, 2]);
+        let c = automata(vec![0, 2, 1]);
+        let population = [&a, &b, &c];
+        // pairs: (a, b) = 0, (a, c) = 2, (b, c) = 2
+        assert_eq!(mean_distance(&population), 4.0 / 3.0);
+        assert_eq!(unique_rules(&population), 2);
+        let h = entropy(&population);
+        assert_eq!(h[0], 0.0);
+        let expected = -(2.0f64 / 3.0) * (2.0f64 / 3.0).log2() - (1.0f64 / 3.0) * (1.0f64 / 3.0).log2();
+        assert!((h[1] - expected).abs() < 1e-12);
+        assert_eq!(h[1], h[2]);
+    }
+
+    #[test]
+    fn identical_population() {
+        let a = automata(vec![0, 1, 2]);
+        let population = [&a, &a];
+        assert_eq!(mean_distance(&population), 0.0);
+        assert_eq!(unique_rules(&population), 1);
+        assert_eq!(entropy(&population), vec![0.0; 3]);
+        assert_eq!(mean_distance(&[]), 0.0);
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut board = Board::initialise(5, 3);
+        board.fill(automata(vec![0, 1, 2]), &[1]);
+        let population = [board];
+        assert!(is_duplicate(&automata(vec![0, 1, 2]), &population));
+        assert!(!is_duplicate(&automata(vec![0, 2, 2]), &population));
+    }
+}
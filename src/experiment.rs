@@ -0,0 +1,611 @@
+extern crate rand;
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::adaptation::{MutationControl, MutationSchedule};
+use crate::automata::QUIESCENT_RULES;
+use crate::board::{make_boards, write_results, Board};
+use crate::cache::FitnessCache;
+use crate::diversity::{diversity, Diversity, DuplicatePolicy};
+use crate::generational::next_generation;
+use crate::hall_of_fame::HallOfFame;
+use crate::lineage::{Genealogy, GraphFormat};
+use crate::mutation::{CrossoverOperator, KPoint, MutationOperator, Uniform};
+use crate::novelty;
+use crate::novelty::{Archive, Objective};
+use crate::palette::Palette;
+use crate::population::{make_1d_population, make_2d_population};
+use crate::report::write_report;
+use crate::selection::Selection;
+use crate::statistics::{write_statistics, EventStatistics, Format};
+use crate::tournament::{mating_event, sample_population, MatingConfig, Replacement};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheme {
+    // steady state: each step is one tournament, which
+    // replaces two boards in the population
+    SteadyState,
+    // generational: each step builds a whole new population,
+    // keeping the best `elitism` boards unchanged
+    Generational { elitism: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    // ran all num_mevs steps
+    MaxMevs,
+    // a board reached the target fitness
+    TargetFitness,
+    // the best fitness did not improve for `patience` steps
+    NoImprovement,
+    // the mean distance between rule strings fell to the
+    // minimum diversity
+    DiversityCollapse,
+    // the run used up its time budget
+    TimeBudget,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            StopReason::MaxMevs => "reached the maximum number of mating events",
+            StopReason::TargetFitness => "reached the target fitness",
+            StopReason::NoImprovement => "no improvement in the best fitness",
+            StopReason::DiversityCollapse => "population diversity collapsed",
+            StopReason::TimeBudget => "ran out of time",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    // number of steps to run (mating events for the steady
+    // state scheme, generations for the generational scheme)
+    pub num_mevs: usize,
+    pub population_size: usize,
+    pub num_states: usize,
+    // size of the steady state sample, and of the tournament
+    // used to pick parents unless another selection is given
+    pub tournament_size: usize,
+    pub neighbourhood_size: u8,
+    pub is_2d: bool,
+    pub width: usize,
+    pub height: usize,
+    // how many layers to make for 2D boards (1 for 1D boards)
+    pub num_iters: usize,
+    pub start_population: Vec<usize>,
+    pub crossover: CrossoverOperator,
+    pub max_mutations: usize,
+    // how the number of mutations changes during the run
+    pub mutation_schedule: MutationSchedule,
+    // mutations applied to each child, one after the other
+    pub mutation_operators: Vec<MutationOperator>,
+    // rule string positions that crossover and mutation
+    // never change
+    pub locked_positions: Vec<usize>,
+    pub scheme: Scheme,
+    // how parents are picked: from the tournament sample for
+    // the steady state scheme, from the whole population for
+    // the generational scheme; None is a tournament of
+    // tournament_size, so the two cannot disagree
+    pub selection: Option<Selection>,
+    // which boards the children replace (steady state only,
+    // the generational scheme replaces every non-elite board)
+    pub replacement: Replacement,
+    // what to do with children that are already in the
+    // population (steady state only)
+    pub duplicates: DuplicatePolicy,
+    pub objective: Objective,
+    // number of evaluated boards kept to avoid running the same
+    // rule string twice (0 turns the cache off)
+    pub cache_size: usize,
+    // parameters for the novelty archive (only used when
+    // the objective is not fitness)
+    pub novelty_neighbours: usize,
+    pub novelty_threshold: f64,
+    pub archive_size: usize,
+    // number of distinct apoptotic automata kept in the
+    // hall of fame
+    pub hall_of_fame_size: usize,
+    // conditions for stopping before num_mevs steps (None
+    // turns a condition off): a board reaching the target
+    // fitness, the best fitness not improving for `patience`
+    // steps, the mean distance between rule strings falling
+    // to `min_diversity`, or the run taking longer than the
+    // time budget (which needs a clock, so is not available
+    // when compiled to wasm)
+    pub target_fitness: Option<usize>,
+    pub patience: Option<usize>,
+    pub min_diversity: Option<f64>,
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        // same values as the 2D example in the integration tests
+        Config {
+            num_mevs: 10,
+            population_size: 10,
+            num_states: 10,
+            tournament_size: 7,
+            neighbourhood_size: 1,
+            is_2d: true,
+            width: 51,
+            height: 51,
+            num_iters: 50,
+            start_population: vec![0, 1, 0, 1, 2, 1, 0, 1, 0],
+            crossover: CrossoverOperator::KPoint(KPoint { max_cuts: 5 }),
+            max_mutations: 15,
+            mutation_schedule: MutationSchedule::Fixed,
+            mutation_operators: vec![MutationOperator::Point],
+            locked_positions: QUIESCENT_RULES.to_vec(),
+            scheme: Scheme::SteadyState,
+            selection: None,
+            replacement: Replacement::Worst,
+            duplicates: DuplicatePolicy::Allow,
+            objective: Objective::Fitness,
+            cache_size: 1000,
+            novelty_neighbours: 15,
+            novelty_threshold: 0.0,
+            archive_size: 100,
+            hall_of_fame_size: 10,
+            target_fitness: None,
+            patience: None,
+            min_diversity: None,
+            time_budget: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn rule_string_length(&self) -> usize {
+        let n_width = 2 * self.neighbourhood_size as usize + 1;
+        let cells = if self.is_2d { n_width * n_width } else { n_width };
+        (self.num_states.max(1) - 1) * cells + 1
+    }
+
+    pub fn parent_selection(&self) -> Selection {
+        // a tournament as big as the sample picks the best two
+        self.selection.unwrap_or(Selection::Tournament(self.tournament_size))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        // catch settings that would make the experiment panic
+        if self.num_states == 0 {
+            return Err("there must be at least one state".to_string());
+        }
+        if self.tournament_size < 2 || self.population_size < self.tournament_size {
+            return Err("the tournament size must be at least 2 and at most the population size".to_string());
+        }
+        match self.selection {
+            // this also keeps tournaments to at least 2
+            Some(Selection::Tournament(k)) if k != self.tournament_size => {
+                return Err(format!("a tournament of {} does not match the tournament size {}", k, self.tournament_size));
+            }
+            Some(Selection::Truncation(fraction)) if !(0.0 < fraction && fraction <= 1.0) => {
+                return Err("the truncation fraction must be above 0 and at most 1".to_string());
+            }
+            Some(Selection::Boltzmann(temperature)) if temperature.is_nan() || temperature <= 0.0 => {
+                return Err("the Boltzmann temperature must be above 0".to_string());
+            }
+            _ => {}
+        }
+        if let Scheme::Generational { elitism } = self.scheme {
+            if self.population_size < elitism {
+                return Err("there cannot be more elite boards than the population size".to_string());
+            }
+        }
+        if let Objective::Blend(weight) = self.objective {
+            if !(0.0..=1.0).contains(&weight) {
+                return Err("the novelty weight must be between 0 and 1".to_string());
+            }
+        }
+        if self.novelty_neighbours == 0 {
+            return Err("novelty needs at least one neighbour".to_string());
+        }
+        if let Some(state) = self.start_population.iter().find(|s| self.num_states <= **s) {
+            return Err(format!("start population state {} is not below the number of states", state));
+        }
+        let start_width = if self.is_2d {
+            let width = (self.start_population.len() as f64).sqrt() as usize;
+            if width * width != self.start_population.len() {
+                return Err("a 2D start population must have a square number of cells".to_string());
+            }
+            width
+        } else {
+            self.start_population.len()
+        };
+        if self.width < start_width || (self.is_2d && self.height < start_width) {
+            return Err("the start population does not fit on the board".to_string());
+        }
+        if self.height == 0 || self.num_iters == 0 {
+            return Err("the board needs at least one row and iteration".to_string());
+        }
+        // every call to next_board works out all the rows of a
+        // 1D board again, adding to its fitness each time
+        if !self.is_2d && self.num_iters != 1 {
+            return Err("a 1D board is made in a single iteration".to_string());
+        }
+        let length = self.rule_string_length();
+        match self.crossover {
+            CrossoverOperator::KPoint(KPoint { max_cuts }) if length <= max_cuts => {
+                return Err(format!("there can be at most {} cuts in a rule string of length {}", length - 1, length));
+            }
+            CrossoverOperator::Uniform(Uniform { swap_probability }) if !(0.0..=1.0).contains(&swap_probability) => {
+                return Err("the swap probability must be between 0 and 1".to_string());
+            }
+            _ => {}
+        }
+        match self.mutation_schedule {
+            MutationSchedule::Fixed if length < self.max_mutations => {
+                return Err(format!("there can be at most {} mutations in a rule string of length {}", length, length));
+            }
+            MutationSchedule::OneFifth { factor, .. } if factor.is_nan() || factor < 1.0 => {
+                return Err("the 1/5th rule factor must be at least 1".to_string());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn fit_defaults(&mut self, num_iters: Option<usize>, max_cuts: Option<usize>, max_mutations: Option<usize>) {
+        // for the front ends: the numbers the user gave are kept
+        // as they are (validate says if they do not fit), while
+        // the defaults, which are meant for 2D boards with long
+        // rule strings, are fitted to the board: 1D boards take
+        // a single iteration and short rule strings take fewer
+        // cuts and mutations
+        if !self.is_2d {
+            self.num_iters = 1;
+        }
+        self.num_iters = num_iters.unwrap_or(self.num_iters);
+        let length = self.rule_string_length();
+        if let Some(max_cuts) = max_cuts {
+            self.crossover = CrossoverOperator::KPoint(KPoint { max_cuts });
+        } else if let CrossoverOperator::KPoint(KPoint { max_cuts }) = self.crossover {
+            self.crossover = CrossoverOperator::KPoint(KPoint { max_cuts: max_cuts.min(length - 1) });
+        }
+        self.max_mutations = max_mutations.unwrap_or_else(|| self.max_mutations.min(length));
+    }
+}
+
+#[derive(Debug)]
+pub struct Experiment {
+    pub config: Config,
+    pub boards: Vec<Board>,
+    pub archive: Archive,
+    pub mutation: MutationControl,
+    pub cache: FitnessCache,
+    // number of steps run so far
+    pub mev: usize,
+    // number of boards evaluated (including the initial population,
+    // but not boards copied from the cache), used to compare schemes
+    // that replace different numbers of boards per step
+    pub evaluations: usize,
+    // number of children that went into the population
+    // and number that were thrown away
+    pub replaced: usize,
+    pub rejected: usize,
+    // number of children thrown away for being duplicates
+    pub duplicates: usize,
+    // diversity of the population after the last step
+    pub diversity: Diversity,
+    // statistics recorded after every step
+    pub history: Vec<EventStatistics>,
+    // best apoptotic automata seen over the whole run
+    pub hall_of_fame: HallOfFame,
+    // every automata that has been in the population
+    pub genealogy: Genealogy,
+    // best fitness seen so far and the step it was first seen at
+    pub best_fitness: usize,
+    pub last_improvement: usize,
+    // when the first step was run (only kept if there is a
+    // time budget)
+    started: Option<Instant>,
+    // why the run stopped, once it has
+    pub stop_reason: Option<StopReason>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunStatistics {
+    pub mevs: usize,
+    pub evaluations: usize,
+    pub scheme: Scheme,
+    pub replacement: Replacement,
+    pub replaced: usize,
+    pub rejected: usize,
+    pub duplicates: usize,
+    // 0 when the cache is off
+    pub cache_size: usize,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    pub best_fitness: usize,
+    pub mean_fitness: f64,
+    pub num_apoptotic: usize,
+    pub stop_reason: Option<StopReason>,
+}
+
+impl fmt::Display for RunStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "mating events: {}", self.mevs)?;
+        writeln!(f, "evaluations: {}", self.evaluations)?;
+        writeln!(f, "scheme: {:?}", self.scheme)?;
+        writeln!(f, "replacement: {:?}", self.replacement)?;
+        writeln!(f, "children replaced: {}", self.replaced)?;
+        writeln!(f, "children rejected: {}", self.rejected)?;
+        writeln!(f, "duplicate children: {}", self.duplicates)?;
+        let lookups = self.cache_hits + self.cache_misses;
+        if self.cache_size == 0 {
+            writeln!(f, "cache hits: disabled")?;
+        } else if lookups != 0 {
+            let hit_rate = 100.0 * self.cache_hits as f64 / lookups as f64;
+            writeln!(f, "cache hits: {} of {} ({:.1}%)", self.cache_hits, lookups, hit_rate)?;
+        }
+        writeln!(f, "best fitness: {}", self.best_fitness)?;
+        writeln!(f, "mean fitness: {:.3}", self.mean_fitness)?;
+        write!(f, "apoptotic boards: {}", self.num_apoptotic)?;
+        if let Some(reason) = self.stop_reason {
+            write!(f, "\nstopped: {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Experiment {
+    pub fn new(config: Config) -> Experiment {
+        let mut population = if config.is_2d {
+            make_2d_population(config.population_size, config.num_states, config.neighbourhood_size)
+        } else {
+            make_1d_population(config.population_size, config.num_states, config.neighbourhood_size)
+        };
+        // self-adaptive rates start out making the same number of
+        // mutations on average as a uniform draw up to max_mutations
+        for automata in population.iter_mut() {
+            let length = automata.rule_string.len() as f64;
+            automata.mutation_rate = (config.max_mutations as f64 / 2.0 / length).max(1.0 / length).min(0.5);
+        }
+        let mut cache = FitnessCache::new(config.cache_size);
+        let boards = make_boards(
+            population, config.width, config.height, config.num_iters, &config.start_population, &mut cache
+        );
+        let archive = Archive::new(config.novelty_neighbours, config.novelty_threshold, config.archive_size);
+        let diversity = diversity(&boards);
+        let mut mutation = MutationControl::new(config.mutation_schedule, config.max_mutations);
+        mutation.operators = config.mutation_operators.clone();
+        let mut hall_of_fame = HallOfFame::new(config.hall_of_fame_size);
+        let everyone: Vec<usize> = (0..boards.len()).collect();
+        hall_of_fame.update(&boards, &everyone, 0);
+        let mut genealogy = Genealogy::new();
+        genealogy.update(&boards, &everyone, 0);
+        let best_fitness = boards.iter().map(|b| b.fitness).max().unwrap_or(0);
+        Experiment {
+            evaluations: cache.evaluated,
+            config,
+            boards,
+            archive,
+            mutation,
+            cache,
+            mev: 0,
+            replaced: 0,
+            rejected: 0,
+            duplicates: 0,
+            diversity,
+            history: Vec::new(),
+            hall_of_fame,
+            genealogy,
+            best_fitness,
+            last_improvement: 0,
+            started: None,
+            stop_reason: None,
+        }
+    }
+
+    pub fn step(&mut self) {
+        // the time budget covers running the steps, not
+        // setting up the experiment
+        if self.config.time_budget.is_some() && self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+        let evaluated = self.cache.evaluated;
+        let replaced = match self.config.scheme {
+            Scheme::SteadyState => self.tournament(),
+            Scheme::Generational { elitism } => self.generation(elitism),
+        };
+        self.evaluations += self.cache.evaluated - evaluated;
+        self.mutation.step();
+        self.diversity = diversity(&self.boards);
+        self.mev += 1;
+        self.hall_of_fame.update(&self.boards, &replaced, self.mev);
+        self.genealogy.update(&self.boards, &replaced, self.mev);
+        let best_fitness = self.best().fitness;
+        if self.best_fitness < best_fitness {
+            self.best_fitness = best_fitness;
+            self.last_improvement = self.mev;
+        }
+        let event = EventStatistics::new(self.mev, &self.boards, &self.diversity, replaced);
+        self.history.push(event);
+    }
+
+    fn tournament(&mut self) -> Vec<usize> {
+        // pick parents from a random sample and put their
+        // children back according to the replacement strategy
+        let config = &self.config;
+        assert!(config.tournament_size <= self.boards.len());
+
+        let sample = sample_population(config.tournament_size, self.boards.len());
+        let scores = novelty::scores(&self.boards, &sample, &self.archive, config.objective);
+        let mating = MatingConfig {
+            selection: config.parent_selection(),
+            crossover: config.crossover,
+            duplicates: config.duplicates,
+            locked_positions: &config.locked_positions,
+            start_population: &config.start_population,
+            num_iters: config.num_iters,
+            replacement: config.replacement,
+        };
+        let event = mating_event(&mut self.boards, &sample, &scores, &mating, &mut self.mutation, &mut self.cache);
+        self.duplicates += event.duplicates;
+        self.rejected += event.rejected;
+        self.replaced += event.replaced.len();
+        if config.objective != Objective::Fitness {
+            novelty::update_archive(&mut self.archive, &self.boards, &event.replaced);
+        }
+        event.replaced
+    }
+
+    fn generation(&mut self, elitism: usize) -> Vec<usize> {
+        let config = &self.config;
+        let everyone: Vec<usize> = (0..self.boards.len()).collect();
+        let scores = novelty::scores(&self.boards, &everyone, &self.archive, config.objective);
+        let replaced = next_generation(
+            &mut self.boards,
+            &scores,
+            config.parent_selection(),
+            elitism,
+            config.crossover,
+            &mut self.mutation,
+            &config.locked_positions,
+            &config.start_population,
+            config.num_iters,
+            &mut self.cache,
+        );
+        if config.objective != Objective::Fitness {
+            novelty::update_archive(&mut self.archive, &self.boards, &replaced);
+        }
+        self.replaced += replaced.len();
+        replaced
+    }
+
+    pub fn run(&mut self) {
+        while !self.finished() {
+            self.step();
+        }
+    }
+
+    pub fn finished(&mut self) -> bool {
+        // true once a stopping condition has been met; the
+        // first condition met is kept as the stop reason
+        if self.stop_reason.is_none() {
+            self.stop_reason = self.should_stop();
+        }
+        self.stop_reason.is_some()
+    }
+
+    pub fn should_stop(&self) -> Option<StopReason> {
+        let config = &self.config;
+        if let Some(target) = config.target_fitness {
+            if target <= self.best_fitness {
+                return Some(StopReason::TargetFitness);
+            }
+        }
+        if let Some(patience) = config.patience {
+            if patience <= self.mev - self.last_improvement {
+                return Some(StopReason::NoImprovement);
+            }
+        }
+        if let Some(min_diversity) = config.min_diversity {
+            if self.diversity.mean_distance <= min_diversity {
+                return Some(StopReason::DiversityCollapse);
+            }
+        }
+        if let (Some(budget), Some(started)) = (config.time_budget, self.started) {
+            if budget <= started.elapsed() {
+                return Some(StopReason::TimeBudget);
+            }
+        }
+        if config.num_mevs <= self.mev {
+            return Some(StopReason::MaxMevs);
+        }
+        None
+    }
+
+    pub fn best(&self) -> &Board {
+        // board with the highest fitness (the first one
+        // found if there is a tie)
+        let mut best = &self.boards[0];
+        for board in &self.boards[1..] {
+            if best.fitness < board.fitness {
+                best = board;
+            }
+        }
+        best
+    }
+
+    pub fn mean_fitness(&self) -> f64 {
+        let total: usize = self.boards.iter().map(|b| b.fitness).sum();
+        total as f64 / self.boards.len() as f64
+    }
+
+    pub fn write_statistics(&self, output_file: &str, format: Format) -> std::io::Result<()> {
+        write_statistics(&self.history, output_file, format)
+    }
+
+    pub fn write_results(&self, population_file: &str, hall_of_fame_file: &str) -> std::io::Result<()> {
+        // the final population and the hall of fame, side by side
+        write_results(&self.boards, population_file)?;
+        self.hall_of_fame.write(hall_of_fame_file)
+    }
+
+    pub fn write_report(&self, output_file: &str, palette: &Palette, num_automata: usize) -> std::io::Result<()> {
+        // a standalone html page with the configuration, plots
+        // of the run and the top num_automata automata
+        write_report(self, output_file, palette, num_automata)
+    }
+
+    pub fn write_genealogy(&self, output_file: &str, format: GraphFormat) -> std::io::Result<()> {
+        self.genealogy.write(output_file, format)
+    }
+
+    pub fn statistics(&self) -> RunStatistics {
+        RunStatistics {
+            mevs: self.mev,
+            evaluations: self.evaluations,
+            scheme: self.config.scheme,
+            replacement: self.config.replacement,
+            replaced: self.replaced,
+            rejected: self.rejected,
+            duplicates: self.duplicates,
+            cache_size: self.cache.capacity,
+            cache_hits: self.cache.hits,
+            cache_misses: self.cache.misses,
+            best_fitness: self.best().fitness,
+            mean_fitness: self.mean_fitness(),
+            num_apoptotic: self.boards.iter().filter(|b| b.apoptotic).count(),
+            stop_reason: self.stop_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_starts_on_first_step() {
+        let config = Config {
+            num_states: 3,
+            population_size: 5,
+            tournament_size: 4,
+            width: 15,
+            height: 15,
+            num_iters: 8,
+            time_budget: Some(Duration::from_secs(60)),
+            ..Config::default()
+        };
+        let mut experiment = Experiment::new(config.clone());
+        assert!(experiment.started.is_none());
+        let before = Instant::now();
+        experiment.step();
+        let started = experiment.started.unwrap();
+        assert!(before <= started);
+        // later steps keep the same start
+        experiment.step();
+        assert_eq!(experiment.started, Some(started));
+        // without a budget there is no clock
+        let mut experiment = Experiment::new(Config { time_budget: None, ..config });
+        experiment.step();
+        assert!(experiment.started.is_none());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::adaptation::{MutationControl, MutationSchedule};
+use crate::automata::QUIESCENT_RULES;
+use crate::board::Board as Board;
+use crate::cache::FitnessCache;
+use crate::mutation::{CrossoverOperator, KPoint};
+use crate::selection::Selection;
+use crate::tournament::breed;
+
+pub fn generation(
+    tournament_size: usize,
+    population: &mut [Board],
+    elitism: usize,
+    max_cuts: usize,
+    max_mutations: usize,
+    start_population: &[usize],
+    num_iters: usize,
+) -> Vec<usize> {
+    // replace the whole population (apart from the best
+    // elitism boards) with children of the current population,
+    // picking parents with tournaments of tournament_size
+    let fitness_values: Vec<f64> = population.iter().map(|b| b.fitness as f64).collect();
+    next_generation(
+        population,
+        &fitness_values,
+        Selection::Tournament(tournament_size),
+        elitism,
+        CrossoverOperator::KPoint(KPoint { max_cuts }),
+        &mut MutationControl::new(MutationSchedule::Fixed, max_mutations),
+        &QUIESCENT_RULES,
+        start_population,
+        num_iters,
+        &mut FitnessCache::new(0),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn next_generation(
+    population: &mut [Board],
+    scores: &[f64],
+    selection: Selection,
+    elitism: usize,
+    operator: CrossoverOperator,
+    mutation: &mut MutationControl,
+    locked: &[usize],
+    start_population: &[usize],
+    num_iters: usize,
+    cache: &mut FitnessCache,
+) -> Vec<usize> {
+    // returns the indices of the boards that were replaced
+    assert!(elitism <= population.len());
+    assert_eq!(scores.len(), population.len());
+    let mut rng = rand::thread_rng();
+
+    let replaced = ranking(scores).split_off(elitism);
+
+    // all children are made before any board is replaced, so
+    // parents always come from the previous generation; each
+    // child is kept with the fitness of its parent
+    let mut children = Vec::with_capacity(replaced.len() + 1);
+    while children.len() < replaced.len() {
+        let (parent1, parent2) = selection.select_pair(scores, &mut rng);
+        let (child1, child2) = breed(population, parent1, parent2, &operator, mutation, locked);
+        children.push((child1, population[parent1].fitness));
+        children.push((child2, population[parent2].fitness));
+    }
+    // an odd number of replacements leaves one spare child
+    children.truncate(replaced.len());
+
+    for (i, (child, parent_fitness)) in replaced.iter().zip(children) {
+        let (width, height) = (population[*i].width(), population[*i].height());
+        population[*i] = cache.evaluate(child, width, height, start_population, num_iters);
+        mutation.record(parent_fitness < population[*i].fitness);
+    }
+    replaced
+}
+
+pub(crate) fn ranking(scores: &[f64]) -> Vec<usize> {
+    // indices of the scores from best to worst; the sort is
+    // stable so ties keep their position in the population
+    let mut ranking: Vec<usize> = (0..scores.len()).collect();
+    ranking.sort_by(|a, b| scores[*b].partial_cmp(&scores[*a]).unwrap());
+    ranking
+}
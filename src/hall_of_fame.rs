@@ -0,0 +1,144 @@
+use crate::automata::Automata as Automata;
+use crate::board::Board as Board;
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub automata: Automata,
+    pub fitness: usize,
+    // mating event the automata was first seen at (0 for
+    // the initial population)
+    pub mev: usize,
+}
+
+impl Entry {
+    pub fn result(&self) -> String {
+        // rule string, fitness, mating event, id and parent ids,
+        // in the same style as board::Board::result
+        let rules: Vec<String> = self.automata.rule_string.iter().map(|c| c.to_string()).collect();
+        let parents: Vec<String> = self.automata.parents.iter().map(|p| p.to_string()).collect();
+        format!(
+            "{}, {}, {}, {}, {}",
+            rules.join(""),
+            self.fitness,
+            self.mev,
+            self.automata.id,
+            parents.join(";")
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HallOfFame {
+    // maximum number of automata kept (0 keeps none)
+    pub capacity: usize,
+    // best first; no two entries share a rule string
+    pub entries: Vec<Entry>,
+}
+
+impl HallOfFame {
+    pub fn new(capacity: usize) -> HallOfFame {
+        HallOfFame {
+            capacity,
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn consider(&mut self, board: &Board, mev: usize) -> bool {
+        // add the board's automata if it is apoptotic and good
+        // enough; returns true if it went in
+        if !board.apoptotic || board.fitness == 0 || self.capacity == 0 {
+            return false;
+        }
+        let automata = match &board.automata {
+            Some(automata) => automata,
+            None => return false,
+        };
+        // a rule string found again keeps its first discovery
+        if self.entries.iter().any(|e| e.automata.rule_string == automata.rule_string) {
+            return false;
+        }
+        if self.entries.len() == self.capacity && board.fitness <= self.entries[self.capacity - 1].fitness {
+            return false;
+        }
+        // ties go after the entries already there
+        let position = self.entries.iter().position(|e| e.fitness < board.fitness).unwrap_or(self.entries.len());
+        self.entries.insert(
+            position,
+            Entry {
+                automata: automata.clone(),
+                fitness: board.fitness,
+                mev,
+            },
+        );
+        self.entries.truncate(self.capacity);
+        true
+    }
+
+    pub fn update(&mut self, boards: &[Board], indices: &[usize], mev: usize) {
+        for &i in indices {
+            self.consider(&boards[i], mev);
+        }
+    }
+
+    pub fn write(&self, output_file: &str) -> std::io::Result<()> {
+        let mut results = String::new();
+        for entry in &self.entries {
+            results.push_str(&entry.result());
+            results.push('\n');
+        }
+        std::fs::write(output_file, results)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(rule_string: Vec<usize>, fitness: usize, apoptotic: bool) -> Board {
+        let mut a1d = Automata::new1d(3, 0);
+        a1d.rule_string = rule_string;
+        let mut board = Board::initialise(5, 3);
+        board.fill(a1d, &[1]);
+        board.fitness = fitness;
+        board.apoptotic = apoptotic;
+        board
+    }
+
+    #[test]
+    fn keeps_best_distinct() {
+        let mut hall_of_fame = HallOfFame::new(2);
+        assert!(hall_of_fame.consider(&board(vec![0, 1, 2], 5, true), 0));
+        // not apoptotic
+        assert!(!hall_of_fame.consider(&board(vec![0, 2, 2], 9, false), 1));
+        // same rules as an entry already there
+        assert!(!hall_of_fame.consider(&board(vec![0, 1, 2], 7, true), 2));
+        assert!(hall_of_fame.consider(&board(vec![0, 2, 1], 8, true), 3));
+        assert!(hall_of_fame.consider(&board(vec![0, 1, 1], 6, true), 4));
+        // not better than the worst entry
+        assert!(!hall_of_fame.consider(&board(vec![0, 2, 0], 6, true), 5));
+        let fitness: Vec<usize> = hall_of_fame.entries.iter().map(|e| e.fitness).collect();
+        assert_eq!(fitness, vec![8, 6]);
+        assert_eq!(hall_of_fame.entries[0].mev, 3);
+    }
+
+    #[test]
+    fn entry_result() {
+        let mut hall_of_fame = HallOfFame::new(1);
+        let mut board = board(vec![0, 1, 2], 5, true);
+        if let Some(a) = board.automata.as_mut() {
+            a.id = 12;
+            a.parents = vec![3, 4];
+        }
+        hall_of_fame.consider(&board, 2);
+        assert_eq!(hall_of_fame.entries[0].result(), "012, 5, 2, 12, 3;4");
+    }
+}
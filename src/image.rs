@@ -0,0 +1,175 @@
+extern crate png;
+
+use std::io::Write;
+
+use crate::colour::Colour as Colour;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    // red, green, blue and alpha bytes for each pixel,
+    // row by row from the top left
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn from_cells(cell_states: &[Vec<usize>], colours: &[Colour], scale: usize) -> Image {
+        // each cell becomes a scale by scale square of pixels
+        // in the colour of its state
+        assert!(0 < scale);
+        let rows = cell_states.len();
+        let columns = cell_states.first().map_or(0, |row| row.len());
+        let width = columns * scale;
+        let height = rows * scale;
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for row in cell_states {
+            let mut line = Vec::with_capacity(width * 4);
+            for state in row {
+                let c = colours.get(*state).expect("Not enough colours for all cell states");
+                let rgba = c.to_rgba();
+                for _ in 0..scale {
+                    line.extend_from_slice(&rgba);
+                }
+            }
+            for _ in 0..scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        Image { width, height, pixels }
+    }
+
+    pub fn encode_png<W: Write>(&self, writer: W) -> std::io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    pub fn write_png(&self, output_file: &str) -> std::io::Result<()> {
+        let file = std::fs::File::create(output_file)?;
+        self.encode_png(std::io::BufWriter::new(file))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<Image>,
+    // how long each frame is shown for, in milliseconds
+    pub delays: Vec<u16>,
+    // number of times to play the animation (0 loops forever)
+    pub num_plays: u32,
+}
+
+impl Animation {
+    pub fn from_layers(layers: &[Vec<Vec<usize>>], colours: &[Colour], scale: usize, delay: u16) -> Animation {
+        // one frame per layer, each shown for the same time
+        Animation {
+            frames: layers.iter().map(|layer| Image::from_cells(layer, colours, scale)).collect(),
+            delays: vec![delay; layers.len()],
+            num_plays: 0,
+        }
+    }
+
+    pub fn encode_apng<W: Write>(&self, writer: W) -> std::io::Result<()> {
+        self.check()?;
+        let (width, height) = (self.frames[0].width, self.frames[0].height);
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, self.num_plays)?;
+        let mut writer = encoder.write_header()?;
+        for (frame, delay) in self.frames.iter().zip(&self.delays) {
+            writer.set_frame_delay(*delay, 1000)?;
+            writer.write_image_data(&frame.pixels)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    pub fn write_apng(&self, output_file: &str) -> std::io::Result<()> {
+        // checked first so a bad animation leaves no file behind
+        self.check()?;
+        let file = std::fs::File::create(output_file)?;
+        self.encode_apng(std::io::BufWriter::new(file))
+    }
+
+    fn check(&self) -> std::io::Result<()> {
+        // every frame needs a delay and all frames must be the
+        // same size as the first
+        let invalid = |message: &str| Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, message));
+        let first = match self.frames.first() {
+            Some(frame) => frame,
+            None => return invalid("an animation needs at least one frame"),
+        };
+        if self.frames.len() != self.delays.len() {
+            return invalid("an animation needs one delay per frame");
+        }
+        if self.frames.iter().any(|f| (f.width, f.height) != (first.width, first.height)) {
+            return invalid("all frames of an animation must be the same size");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colours() -> Vec<Colour> {
+        vec![Colour::new(0, 0, 0, 1.0), Colour::new(255, 0, 0, 0.5)]
+    }
+
+    #[test]
+    fn scaled_cells() {
+        let image = Image::from_cells(&[vec![0, 1], vec![1, 0]], &colours(), 2);
+        assert_eq!((image.width, image.height), (4, 4));
+        assert_eq!(image.pixels.len(), 4 * 4 * 4);
+        // top row: two black pixels then two red ones
+        assert_eq!(&image.pixels[..16], &[0, 0, 0, 255, 0, 0, 0, 255, 255, 0, 0, 128, 255, 0, 0, 128]);
+        assert_eq!(&image.pixels[16..32], &image.pixels[..16]);
+        assert_eq!(&image.pixels[32..36], &[255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn png_signature() {
+        let image = Image::from_cells(&[vec![0, 1, 0]], &colours(), 3);
+        let mut bytes = Vec::new();
+        image.encode_png(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+        // the header gives the scaled width and height
+        assert_eq!(&bytes[16..24], &[0, 0, 0, 9, 0, 0, 0, 3]);
+    }
+
+    #[test]
+    fn apng_frames() {
+        let layers = vec![vec![vec![0, 1]], vec![vec![1, 0]], vec![vec![0, 0]]];
+        let animation = Animation::from_layers(&layers, &colours(), 2, 100);
+        assert_eq!(animation.frames.len(), 3);
+        assert_eq!(animation.frames[1].pixels[..4], [255, 0, 0, 128]);
+        let mut bytes = Vec::new();
+        animation.encode_apng(&mut bytes).unwrap();
+        // the animation control chunk gives the number of frames
+        let actl = bytes.windows(4).position(|w| w == b"acTL").unwrap();
+        assert_eq!(&bytes[actl + 4..actl + 8], &[0, 0, 0, 3]);
+        assert_eq!(bytes.windows(4).filter(|w| *w == b"fcTL").count(), 3);
+    }
+
+    #[test]
+    fn invalid_animations() {
+        let frame = Image::from_cells(&[vec![0, 1]], &colours(), 1);
+        let bigger = Image::from_cells(&[vec![0, 1]], &colours(), 2);
+        let animations = [
+            Animation { frames: vec![], delays: vec![], num_plays: 0 },
+            Animation { frames: vec![frame.clone()], delays: vec![], num_plays: 0 },
+            Animation { frames: vec![frame, bigger], delays: vec![100, 100], num_plays: 0 },
+        ];
+        for animation in animations.iter() {
+            let error = animation.encode_apng(Vec::new()).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+}
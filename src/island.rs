@@ -0,0 +1,135 @@
+use crate::automata::Automata as Automata;
+use crate::board::Board as Board;
+use crate::experiment::{Config, Experiment};
+use crate::generational::ranking;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    // each island sends migrants to the next one, and the
+    // last island sends migrants to the first
+    Ring,
+    // each island sends migrants to every other island
+    FullyConnected,
+}
+
+#[derive(Debug)]
+pub struct Archipelago {
+    // every island is an experiment with its own parameters
+    // (e.g. different mutation rates), but all islands must
+    // use the same kind of automata
+    pub islands: Vec<Experiment>,
+    pub topology: Topology,
+    // number of steps between migrations (0 means never)
+    pub migration_interval: usize,
+    // how many of the best rule strings each island sends
+    pub num_migrants: usize,
+    // number of steps run so far
+    pub mev: usize,
+    pub migrations: usize,
+    // step at which the last migration happened
+    last_migration: usize,
+}
+
+impl Archipelago {
+    pub fn new(configs: Vec<Config>, topology: Topology, migration_interval: usize, num_migrants: usize) -> Archipelago {
+        assert!(!configs.is_empty());
+        for config in &configs[1..] {
+            assert_eq!(config.is_2d, configs[0].is_2d);
+            assert_eq!(config.num_states, configs[0].num_states);
+            assert_eq!(config.neighbourhood_size, configs[0].neighbourhood_size);
+        }
+        let islands = configs.into_iter().map(Experiment::new).collect();
+        Archipelago {
+            islands,
+            topology,
+            migration_interval,
+            num_migrants,
+            mev: 0,
+            migrations: 0,
+            last_migration: 0,
+        }
+    }
+
+    pub fn step(&mut self) {
+        // run one step on every island that has not finished,
+        // then migrate if it is time to do so
+        for island in self.islands.iter_mut() {
+            if !island.finished() {
+                island.step();
+            }
+        }
+        self.mev += 1;
+        if self.migration_interval != 0 && self.mev - self.last_migration == self.migration_interval {
+            self.migrate();
+        }
+    }
+
+    pub fn run(&mut self) {
+        while !self.islands.iter_mut().all(|island| island.finished()) {
+            self.step();
+        }
+    }
+
+    pub fn migrate(&mut self) {
+        // copy the best rule strings of each island into the
+        // islands it is connected to, replacing their worst boards
+        let num_islands = self.islands.len();
+        if num_islands < 2 || self.num_migrants == 0 {
+            return;
+        }
+        let emigrants: Vec<Vec<Automata>> = self.islands.iter().map(|island| self.best_automata(island)).collect();
+
+        let mut immigrants: Vec<Vec<Automata>> = vec![Vec::new(); num_islands];
+        for (source, automata) in emigrants.into_iter().enumerate() {
+            for destination in self.destinations(source) {
+                immigrants[destination].extend(automata.iter().cloned());
+            }
+        }
+
+        for (island, arrivals) in self.islands.iter_mut().zip(immigrants) {
+            let fitness_values: Vec<f64> = island.boards.iter().map(|b| b.fitness as f64).collect();
+            // worst boards first; never replace the whole island
+            let mut worst = ranking(&fitness_values);
+            worst.reverse();
+            let config = &island.config;
+            let evaluated = island.cache.evaluated;
+            for (i, automata) in worst.iter().zip(arrivals).take(island.boards.len() - 1) {
+                island.boards[*i] = island.cache.evaluate(
+                    automata, config.width, config.height, &config.start_population, config.num_iters
+                );
+                island.genealogy.record(&island.boards[*i], island.mev);
+            }
+            island.evaluations += island.cache.evaluated - evaluated;
+        }
+        self.migrations += 1;
+        self.last_migration = self.mev;
+    }
+
+    fn best_automata(&self, island: &Experiment) -> Vec<Automata> {
+        let fitness_values: Vec<f64> = island.boards.iter().map(|b| b.fitness as f64).collect();
+        ranking(&fitness_values)
+            .iter()
+            .take(self.num_migrants)
+            .map(|i| island.boards[*i].automata.clone().expect("Board is missing automata."))
+            .collect()
+    }
+
+    pub fn destinations(&self, source: usize) -> Vec<usize> {
+        let num_islands = self.islands.len();
+        match self.topology {
+            Topology::Ring => vec![(source + 1) % num_islands],
+            Topology::FullyConnected => (0..num_islands).filter(|i| *i != source).collect(),
+        }
+    }
+
+    pub fn best(&self) -> &Board {
+        // best board over all of the islands
+        let mut best = self.islands[0].best();
+        for island in &self.islands[1..] {
+            if best.fitness < island.best().fitness {
+                best = island.best();
+            }
+        }
+        best
+    }
+}
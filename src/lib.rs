@@ -1,10 +1,29 @@
 
+pub mod adaptation;
 pub mod automata;
 pub mod board;
+pub mod cache;
+pub mod canvas;
 pub mod colour;
+pub mod diversity;
+pub mod experiment;
+pub mod generational;
+pub mod hall_of_fame;
+pub mod image;
+pub mod island;
+pub mod lineage;
 pub mod mutation;
+pub mod novelty;
+pub mod palette;
 pub mod population;
+pub mod report;
+pub mod selection;
+pub mod statistics;
+pub mod svg;
+pub mod terminal;
 pub mod tournament;
+pub mod wasm;
+pub mod webgl;
 
 use wasm_bindgen::prelude::*;
 
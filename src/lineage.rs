@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use crate::board::Board as Board;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    // GraphViz, with an edge from each parent to its children
+    Dot,
+    // a single JSON object with a list of nodes
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub id: usize,
+    pub parents: Vec<usize>,
+    pub cut_points: Vec<usize>,
+    pub mutated_positions: Vec<usize>,
+    pub rule_string: Vec<usize>,
+    pub fitness: usize,
+    pub apoptotic: bool,
+    // mating event the automata went into the population
+    // (0 for the initial population)
+    pub mev: usize,
+}
+
+impl Node {
+    fn to_dot(&self) -> String {
+        let mut label = format!("{}\\nfitness {}, mev {}", self.id, self.fitness, self.mev);
+        if !self.cut_points.is_empty() {
+            label.push_str(&format!("\\ncuts {}", join(&self.cut_points, ",")));
+        }
+        if !self.mutated_positions.is_empty() {
+            label.push_str(&format!("\\nmutated {}", join(&self.mutated_positions, ",")));
+        }
+        let style = if self.apoptotic { ", style=filled" } else { "" };
+        format!("    n{} [label=\"{}\"{}];", self.id, label, style)
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"id\":{},\"parents\":[{}],\"cut_points\":[{}],\"mutated_positions\":[{}],\"rule_string\":[{}],\"fitness\":{},\"apoptotic\":{},\"mev\":{}}}",
+            self.id,
+            join(&self.parents, ","),
+            join(&self.cut_points, ","),
+            join(&self.mutated_positions, ","),
+            join(&self.rule_string, ","),
+            self.fitness,
+            self.apoptotic,
+            self.mev
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Genealogy {
+    // every automata that has been in the population, by id
+    pub nodes: BTreeMap<usize, Node>,
+}
+
+impl Genealogy {
+    pub fn new() -> Genealogy {
+        Genealogy { nodes: BTreeMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn record(&mut self, board: &Board, mev: usize) {
+        // an automata seen again (e.g. a migrant) keeps
+        // its first record
+        if let Some(automata) = &board.automata {
+            self.nodes.entry(automata.id).or_insert_with(|| Node {
+                id: automata.id,
+                parents: automata.parents.clone(),
+                cut_points: automata.cut_points.clone(),
+                mutated_positions: automata.mutated_positions.clone(),
+                rule_string: automata.rule_string.clone(),
+                fitness: board.fitness,
+                apoptotic: board.apoptotic,
+                mev,
+            });
+        }
+    }
+
+    pub fn update(&mut self, boards: &[Board], indices: &[usize], mev: usize) {
+        for &i in indices {
+            self.record(&boards[i], mev);
+        }
+    }
+
+    pub fn lineage(&self, id: usize) -> Genealogy {
+        // the automata with the given id and all of its
+        // recorded ancestors
+        let mut lineage = Genealogy::new();
+        let mut to_visit = vec![id];
+        while let Some(id) = to_visit.pop() {
+            if lineage.nodes.contains_key(&id) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get(&id) {
+                to_visit.extend(&node.parents);
+                lineage.nodes.insert(id, node.clone());
+            }
+        }
+        lineage
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph genealogy {\n");
+        for node in self.nodes.values() {
+            dot.push_str(&node.to_dot());
+            dot.push('\n');
+        }
+        // parents that were never recorded are left out
+        for node in self.nodes.values() {
+            for parent in &node.parents {
+                if self.nodes.contains_key(parent) {
+                    dot.push_str(&format!("    n{} -> n{};\n", parent, node.id));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self.nodes.values().map(|n| n.to_json()).collect();
+        format!("{{\"nodes\":[{}]}}\n", nodes.join(","))
+    }
+
+    pub fn write(&self, output_file: &str, format: GraphFormat) -> std::io::Result<()> {
+        let contents = match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Json => self.to_json(),
+        };
+        std::fs::write(output_file, contents)?;
+        Ok(())
+    }
+}
+
+fn join(values: &[usize], separator: &str) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    values.join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: usize, parents: Vec<usize>) -> Node {
+        Node {
+            id,
+            parents,
+            cut_points: vec![2],
+            mutated_positions: vec![],
+            rule_string: vec![0, 1],
+            fitness: 3,
+            apoptotic: id == 4,
+            mev: 1,
+        }
+    }
+
+    fn genealogy() -> Genealogy {
+        // 1 and 2 are parents of 3; 3 and 2 are parents of 4;
+        // 5 is unrelated
+        let mut genealogy = Genealogy::new();
+        let nodes = [
+            node(1, vec![]),
+            node(2, vec![]),
+            node(3, vec![1, 2]),
+            node(4, vec![3, 2]),
+            node(5, vec![]),
+        ];
+        for n in nodes.iter() {
+            genealogy.nodes.insert(n.id, n.clone());
+        }
+        genealogy
+    }
+
+    #[test]
+    fn ancestors() {
+        let lineage = genealogy().lineage(4);
+        let ids: Vec<usize> = lineage.nodes.keys().cloned().collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert!(genealogy().lineage(99).is_empty());
+    }
+
+    #[test]
+    fn dot_and_json() {
+        let lineage = genealogy().lineage(3);
+        let dot = lineage.to_dot();
+        assert!(dot.starts_with("digraph genealogy {\n"));
+        assert!(dot.contains("    n1 -> n3;\n"));
+        assert!(dot.contains("    n2 -> n3;\n"));
+        assert!(dot.contains("    n3 [label=\"3\\nfitness 3, mev 1\\ncuts 2\"];\n"));
+        assert_eq!(
+            genealogy().lineage(1).to_json(),
+            "{\"nodes\":[{\"id\":1,\"parents\":[],\"cut_points\":[2],\"mutated_positions\":[],\"rule_string\":[0,1],\"fitness\":3,\"apoptotic\":false,\"mev\":1}]}\n"
+        );
+    }
+}
@@ -3,50 +3,275 @@ extern crate rand;
 use rand::seq::SliceRandom;
 use rand::Rng;
 
-use crate::automata::Automata as Automata;
+use crate::automata::{Automata, QUIESCENT_RULES};
 
 pub fn mutate(automata: &mut Automata, max_mutations: usize) {
+    mutate_with_locks(automata, max_mutations, &QUIESCENT_RULES);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MutationOperator {
+    // replace a rule with a uniformly random state
+    Point,
+    // move a rule up or down by one state; at the lowest and
+    // highest states it either wraps around or stays put
+    Creep { wrap: bool },
+    // exchange the rules at two positions
+    Swap,
+    // reverse the order of the rules in a segment
+    Inversion,
+}
+
+pub fn mutate_with_locks(automata: &mut Automata, max_mutations: usize, locked: &[usize]) {
+    mutate_with_operator(automata, max_mutations, locked, MutationOperator::Point);
+}
+
+pub fn mutate_with_operator(
+    automata: &mut Automata,
+    max_mutations: usize,
+    locked: &[usize],
+    operator: MutationOperator,
+) {
     assert!(max_mutations <= automata.rule_string.len());
     let mut rng = rand::thread_rng();
 
     // choose a random number between 0 and max_mutations inclusive
     let num_mutations = rng.gen_range(0, max_mutations + 1);
+    apply_mutations(automata, num_mutations, locked, operator);
+}
+
+pub fn apply_mutations(
+    automata: &mut Automata,
+    num_mutations: usize,
+    locked: &[usize],
+    operator: MutationOperator,
+) {
+    // perform num_mutations mutations of one kind, never
+    // touching the locked positions; the positions picked are
+    // added to the automata's mutated positions
+    let mut rng = rand::thread_rng();
+    let mut unlocked: Vec<usize> = (0..automata.rule_string.len())
+        .filter(|i| !locked.contains(i))
+        .collect();
+    let num_states = automata.num_states;
+    let rules = &mut automata.rule_string;
+    let mutated = &mut automata.mutated_positions;
 
-    // pick where to perform the mutations (without replacement)
-    let mut mutate_locations: Vec<usize> = (0..automata.rule_string.len()).collect();
-    mutate_locations.shuffle(&mut rng);
-    let mutate_locations = &mutate_locations[..num_mutations];
+    match operator {
+        MutationOperator::Point | MutationOperator::Creep { .. } => {
+            // pick where to perform the mutations (without replacement);
+            // there can be fewer unlocked locations than num_mutations
+            unlocked.shuffle(&mut rng);
+            unlocked.truncate(num_mutations);
 
-    // the only case in which the number of actual mutations is
-    // less than num_mutations is when the random value chosen
-    // is the same as the existing value in the rule string
-    for location in mutate_locations {
-        let random_value = rng.gen_range(0, automata.num_states);
-        automata.rule_string[*location] = random_value;
+            // the only case in which the number of actual mutations is
+            // less than num_mutations is when the random value chosen
+            // is the same as the existing value in the rule string
+            for location in unlocked {
+                mutated.push(location);
+                rules[location] = match operator {
+                    MutationOperator::Creep { wrap } => creep(rules[location], num_states, wrap, rng.gen()),
+                    _ => rng.gen_range(0, num_states),
+                };
+            }
+        }
+        MutationOperator::Swap => {
+            if unlocked.len() < 2 {
+                return;
+            }
+            for _ in 0..num_mutations {
+                let pair: Vec<&usize> = unlocked.choose_multiple(&mut rng, 2).collect();
+                rules.swap(*pair[0], *pair[1]);
+                mutated.push(*pair[0]);
+                mutated.push(*pair[1]);
+            }
+        }
+        MutationOperator::Inversion => {
+            if unlocked.len() < 2 {
+                return;
+            }
+            for _ in 0..num_mutations {
+                // the segment is taken over the unlocked positions,
+                // so locked positions inside it stay where they are
+                let start = rng.gen_range(0, unlocked.len() - 1);
+                let end = rng.gen_range(start + 1, unlocked.len());
+                let values: Vec<usize> = unlocked[start..=end].iter().map(|i| rules[*i]).collect();
+                for (i, value) in unlocked[start..=end].iter().zip(values.into_iter().rev()) {
+                    rules[*i] = value;
+                }
+                mutated.extend(&unlocked[start..=end]);
+            }
+        }
     }
+    mutated.sort();
+    mutated.dedup();
 }
 
-pub fn crossover(automata1: &mut Automata, automata2: &mut Automata, max_cuts: usize) {
+fn creep(state: usize, num_states: usize, wrap: bool, up: bool) -> usize {
+    if up {
+        if state + 1 < num_states {
+            state + 1
+        } else if wrap {
+            0
+        } else {
+            state
+        }
+    } else if 0 < state {
+        state - 1
+    } else if wrap {
+        num_states - 1
+    } else {
+        state
+    }
+}
+
+pub trait Crossover {
+    // exchange parts of the rule strings of two automata
+    // of the same type, in place
+    fn crossover(&self, automata1: &mut Automata, automata2: &mut Automata);
+
+    fn crossover_with_locks(&self, automata1: &mut Automata, automata2: &mut Automata, locked: &[usize]) {
+        // same as crossover, except each child keeps its own
+        // rules at the locked positions
+        let kept: Vec<(usize, usize, usize)> = locked
+            .iter()
+            .filter(|i| **i < automata1.rule_string.len())
+            .map(|i| (*i, automata1.rule_string[*i], automata2.rule_string[*i]))
+            .collect();
+        self.crossover(automata1, automata2);
+        for (i, rule1, rule2) in kept {
+            automata1.rule_string[i] = rule1;
+            automata2.rule_string[i] = rule2;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KPoint {
+    pub max_cuts: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Uniform {
+    // chance that each position is swapped (0.5 for a fair coin)
+    pub swap_probability: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blockwise {
+    pub max_blocks: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrossoverOperator {
+    KPoint(KPoint),
+    Uniform(Uniform),
+    Blockwise(Blockwise),
+}
+
+impl Crossover for KPoint {
+    fn crossover(&self, automata1: &mut Automata, automata2: &mut Automata) {
+        crossover(automata1, automata2, self.max_cuts);
+    }
+}
+
+impl Crossover for Uniform {
+    fn crossover(&self, automata1: &mut Automata, automata2: &mut Automata) {
+        // flip a coin for every position in the rule string
+        assert_same_type(automata1, automata2);
+        assert!((0.0..=1.0).contains(&self.swap_probability));
+        let mut rng = rand::thread_rng();
+        let mut swapped = vec![false; automata1.rule_string.len()];
+        for (i, swap) in swapped.iter_mut().enumerate() {
+            if rng.gen::<f64>() < self.swap_probability {
+                std::mem::swap(&mut automata1.rule_string[i], &mut automata2.rule_string[i]);
+                *swap = true;
+            }
+        }
+        record_cut_points(automata1, automata2, &swapped);
+    }
+}
+
+impl Crossover for Blockwise {
+    fn crossover(&self, automata1: &mut Automata, automata2: &mut Automata) {
+        // the position in a rule string is the neighbourhood sum,
+        // so swapping contiguous blocks keeps the rules for
+        // similar neighbourhood sums together
+        assert_same_type(automata1, automata2);
+        let length = automata1.rule_string.len();
+        let mut rng = rand::thread_rng();
+        let num_blocks = rng.gen_range(0, self.max_blocks + 1);
+        let mut swapped = vec![false; length];
+        for _ in 0..num_blocks {
+            let start = rng.gen_range(0, length);
+            let end = rng.gen_range(start, length) + 1;
+            for i in start..end {
+                std::mem::swap(&mut automata1.rule_string[i], &mut automata2.rule_string[i]);
+            }
+            for swap in &mut swapped[start..end] {
+                *swap = !*swap;
+            }
+        }
+        record_cut_points(automata1, automata2, &swapped);
+    }
+}
+
+impl Crossover for CrossoverOperator {
+    fn crossover(&self, automata1: &mut Automata, automata2: &mut Automata) {
+        match self {
+            CrossoverOperator::KPoint(x) => x.crossover(automata1, automata2),
+            CrossoverOperator::Uniform(x) => x.crossover(automata1, automata2),
+            CrossoverOperator::Blockwise(x) => x.crossover(automata1, automata2),
+        }
+    }
+}
+
+fn record_cut_points(automata1: &mut Automata, automata2: &mut Automata, swapped: &[bool]) {
+    // a cut point is wherever a child switches from taking rules
+    // from one parent to taking them from the other, the same as
+    // the cut locations of k-point crossover
+    let mut cut_points = Vec::new();
+    let mut previous = false;
+    for (i, swap) in swapped.iter().enumerate() {
+        if *swap != previous {
+            cut_points.push(i);
+            previous = *swap;
+        }
+    }
+    automata1.cut_points = cut_points.clone();
+    automata2.cut_points = cut_points;
+}
+
+fn assert_same_type(automata1: &Automata, automata2: &Automata) {
     // make sure the automata are of the same type
     assert_eq!(automata1.is_2d, automata2.is_2d);
     assert_eq!(automata1.neighbourhood_size, automata2.neighbourhood_size);
     assert_eq!(automata1.num_states, automata2.num_states);
+}
+
+pub fn crossover(automata1: &mut Automata, automata2: &mut Automata, max_cuts: usize) {
+    assert_same_type(automata1, automata2);
     assert!(max_cuts < automata1.rule_string.len());
     // how many points to perform crossover
     let mut rng = rand::thread_rng();
     let num_cuts = rng.gen_range(0, max_cuts + 1);
 
-    let mut cut_locations: Vec<usize> = (0..automata1.rule_string.len()).collect();
+    // a cut at i means the rules from i onwards come from the
+    // other parent (until the next cut), so a cut at 0 is not
+    // allowed: it would just swap the parents
+    let mut cut_locations: Vec<usize> = (1..automata1.rule_string.len()).collect();
     cut_locations.shuffle(&mut rng);
 
     // limit size of cut_locations so it matches num_cuts
     // sort the resulting vector
-    // let mut cut_locations: Vec<usize> = &cut_locations[..num_cuts];
-    cut_locations.split_off(num_cuts);
+    cut_locations.truncate(num_cuts);
     cut_locations.sort();
+    automata1.cut_points = cut_locations.clone();
+    automata2.cut_points = cut_locations.clone();
 
     // iterate over elements in rule string, write to a different
-    // vector each time a cut_location is encountered
+    // vector each time a cut_location is encountered; with no
+    // cuts the children are copies of their parents
     let mut rules1 = Vec::with_capacity(automata1.rule_string.len());
     let mut rules2 = Vec::with_capacity(automata1.rule_string.len());
     let mut swap = false;
@@ -55,13 +280,164 @@ pub fn crossover(automata1: &mut Automata, automata2: &mut Automata, max_cuts: u
             swap = !swap;
         }
         if swap {
-            rules1.push(automata1.rule_string[i]);
-            rules2.push(automata2.rule_string[i]);
-        } else {
             rules1.push(automata2.rule_string[i]);
             rules2.push(automata1.rule_string[i]);
+        } else {
+            rules1.push(automata1.rule_string[i]);
+            rules2.push(automata2.rule_string[i]);
         }
     }
     automata1.rule_string = rules1;
     automata2.rule_string = rules2;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parents() -> (Automata, Automata) {
+        let mut a1 = Automata::new1d(3, 2);
+        let mut a2 = Automata::new1d(3, 2);
+        a1.rule_string = vec![1; a1.rule_string.len()];
+        a2.rule_string = vec![2; a2.rule_string.len()];
+        (a1, a2)
+    }
+
+    fn complementary(a1: &Automata, a2: &Automata) -> bool {
+        // every position came from exactly one of the parents
+        a1.rule_string.iter().zip(a2.rule_string.iter()).all(|(x, y)| x + y == 3)
+    }
+
+    #[test]
+    fn zero_cuts_keeps_parents() {
+        let (mut a1, mut a2) = parents();
+        crossover(&mut a1, &mut a2, 0);
+        assert_eq!(a1.rule_string, vec![1; 11]);
+        assert_eq!(a2.rule_string, vec![2; 11]);
+    }
+
+    #[test]
+    fn one_cut() {
+        let (mut a1, mut a2) = parents();
+        // at most 1 cut, repeat until there is one
+        while a1.rule_string == vec![1; 11] {
+            crossover(&mut a1, &mut a2, 1);
+        }
+        assert!(complementary(&a1, &a2));
+        // first rule is never swapped and the rest is one block
+        assert_eq!(a1.rule_string[0], 1);
+        let cut = a1.rule_string.iter().position(|x| *x == 2).unwrap();
+        assert!(a1.rule_string[cut..].iter().all(|x| *x == 2));
+    }
+
+    #[test]
+    fn uniform_probabilities() {
+        let (mut a1, mut a2) = parents();
+        Uniform { swap_probability: 0.0 }.crossover(&mut a1, &mut a2);
+        assert_eq!(a1.rule_string, vec![1; 11]);
+        Uniform { swap_probability: 1.0 }.crossover(&mut a1, &mut a2);
+        assert_eq!(a1.rule_string, vec![2; 11]);
+        assert_eq!(a2.rule_string, vec![1; 11]);
+        Uniform { swap_probability: 0.5 }.crossover(&mut a1, &mut a2);
+        assert!(complementary(&a1, &a2));
+    }
+
+    #[test]
+    fn locked_positions() {
+        let (mut a1, mut a2) = parents();
+        a1.rule_string[0] = 0;
+        a2.rule_string[0] = 0;
+        a2.rule_string[5] = 0;
+        Uniform { swap_probability: 1.0 }.crossover_with_locks(&mut a1, &mut a2, &[0, 5]);
+        assert_eq!(a1.rule_string, vec![0, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(a2.rule_string, vec![0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1]);
+
+        // the quiescent rule is never mutated
+        for _ in 0..50 {
+            let mut a1d = Automata::new1d(5, 1);
+            mutate(&mut a1d, 13);
+            assert_eq!(a1d.validate(), Ok(()));
+        }
+        // every position locked means nothing can change
+        let mut a1d = Automata::new1d(5, 1);
+        let copy_a1d = a1d.clone();
+        let everything: Vec<usize> = (0..13).collect();
+        mutate_with_locks(&mut a1d, 13, &everything);
+        assert_eq!(a1d.rule_string, copy_a1d.rule_string);
+    }
+
+    #[test]
+    fn creep_steps() {
+        assert_eq!(creep(2, 5, false, true), 3);
+        assert_eq!(creep(2, 5, false, false), 1);
+        assert_eq!(creep(4, 5, false, true), 4);
+        assert_eq!(creep(4, 5, true, true), 0);
+        assert_eq!(creep(0, 5, false, false), 0);
+        assert_eq!(creep(0, 5, true, false), 4);
+
+        let mut a1d = Automata::new1d(5, 1);
+        let copy_a1d = a1d.clone();
+        apply_mutations(&mut a1d, 13, &QUIESCENT_RULES, MutationOperator::Creep { wrap: false });
+        for (x, y) in a1d.rule_string.iter().zip(copy_a1d.rule_string.iter()) {
+            assert!((*x as i64 - *y as i64).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn permutation_operators() {
+        // swapping and inverting move rules around without
+        // changing which rules there are
+        for operator in &[MutationOperator::Swap, MutationOperator::Inversion] {
+            let mut a1d = Automata::new1d(5, 1);
+            a1d.rule_string = (0..13).map(|i| i % 5).collect();
+            a1d.rule_string[0] = 0;
+            apply_mutations(&mut a1d, 3, &[0, 12], *operator);
+            assert_eq!(a1d.rule_string[0], 0);
+            assert_eq!(a1d.rule_string[12], 2);
+            let mut sorted = a1d.rule_string.clone();
+            sorted.sort();
+            let mut expected: Vec<usize> = (0..13).map(|i| i % 5).collect();
+            expected.sort();
+            assert_eq!(sorted, expected);
+        }
+
+        let mut a1d = Automata::new1d(5, 1);
+        a1d.rule_string = vec![0, 1, 2, 3];
+        apply_mutations(&mut a1d, 1, &[0], MutationOperator::Inversion);
+        assert!(
+            [vec![0, 2, 1, 3], vec![0, 1, 3, 2], vec![0, 3, 2, 1]].contains(&a1d.rule_string),
+            "{:?}",
+            a1d.rule_string
+        );
+    }
+
+    #[test]
+    fn blockwise_swaps_ranges() {
+        let (mut a1, mut a2) = parents();
+        Blockwise { max_blocks: 0 }.crossover(&mut a1, &mut a2);
+        assert_eq!(a1.rule_string, vec![1; 11]);
+        let operator = CrossoverOperator::Blockwise(Blockwise { max_blocks: 3 });
+        for _ in 0..10 {
+            operator.crossover(&mut a1, &mut a2);
+            assert!(complementary(&a1, &a2));
+        }
+    }
+
+    #[test]
+    fn records_lineage() {
+        let (mut a1, mut a2) = parents();
+        while a1.rule_string == vec![1; 11] {
+            crossover(&mut a1, &mut a2, 1);
+        }
+        let cut = a1.rule_string.iter().position(|x| *x == 2).unwrap();
+        assert_eq!(a1.cut_points, vec![cut]);
+        assert_eq!(a2.cut_points, vec![cut]);
+        Uniform { swap_probability: 1.0 }.crossover(&mut a1, &mut a2);
+        assert_eq!(a1.cut_points, vec![0]);
+
+        let mut a1d = Automata::new1d(5, 1);
+        apply_mutations(&mut a1d, 13, &[0, 4], MutationOperator::Point);
+        let expected: Vec<usize> = (1..13).filter(|i| *i != 4).collect();
+        assert_eq!(a1d.mutated_positions, expected);
+    }
+}
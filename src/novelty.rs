@@ -0,0 +1,208 @@
+use crate::board::Board as Board;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    // select parents on fitness alone (the original behaviour)
+    Fitness,
+    // select parents on how different their behaviour is
+    // from the rest of the population and the archive
+    Novelty,
+    // weighted sum of normalised novelty and fitness, where
+    // the weight (between 0 and 1) is given to novelty
+    Blend(f64),
+}
+
+#[derive(Debug)]
+pub struct Archive {
+    // behaviour descriptors of boards that were novel
+    // enough when they were made, and the ids of their
+    // automata
+    pub behaviours: Vec<Vec<f64>>,
+    pub owners: Vec<usize>,
+    // number of nearest neighbours used for the novelty score
+    pub k: usize,
+    // a new behaviour is only archived if its novelty is
+    // larger than this value
+    pub threshold: f64,
+    // the oldest behaviour is dropped once this size is reached
+    pub capacity: usize,
+}
+
+impl Archive {
+    pub fn new(k: usize, threshold: f64, capacity: usize) -> Archive {
+        assert!(0 < k);
+        Archive {
+            behaviours: Vec::new(),
+            owners: Vec::new(),
+            k,
+            threshold,
+            capacity,
+        }
+    }
+
+    pub fn novelty(&self, behaviour: &[f64], owner: usize, others: &[Vec<f64>]) -> f64 {
+        // mean distance from the behaviour to its k nearest
+        // neighbours, looking at both the other boards and
+        // the archive; the owner's own archived behaviour is
+        // left out, or it would always be its nearest neighbour
+        let archived = self
+            .behaviours
+            .iter()
+            .zip(self.owners.iter())
+            .filter(|(_, id)| **id != owner)
+            .map(|(b, _)| b);
+        let mut distances: Vec<f64> = others
+            .iter()
+            .chain(archived)
+            .map(|other| distance(behaviour, other))
+            .collect();
+        if distances.is_empty() {
+            return 0.0;
+        }
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let k = self.k.min(distances.len());
+        distances[..k].iter().sum::<f64>() / k as f64
+    }
+
+    pub fn consider(&mut self, behaviour: Vec<f64>, owner: usize, novelty: f64) -> bool {
+        // add a behaviour to the archive if it is novel enough,
+        // returns whether or not the behaviour was added
+        if novelty <= self.threshold || self.capacity == 0 {
+            return false;
+        }
+        if self.behaviours.len() == self.capacity {
+            self.behaviours.remove(0);
+            self.owners.remove(0);
+        }
+        self.behaviours.push(behaviour);
+        self.owners.push(owner);
+        true
+    }
+}
+
+pub fn behaviour(board: &Board) -> Vec<f64> {
+    // the behaviour descriptor of a board is the number of
+    // live cells in each generation; 2D boards that stopped
+    // early (i.e. hit the wall) have shorter descriptors
+    board.live_counts.iter().map(|c| *c as f64).collect()
+}
+
+pub fn distance(behaviour1: &[f64], behaviour2: &[f64]) -> f64 {
+    // euclidean distance where the shorter descriptor is
+    // padded with zeros (no live cells after it stopped)
+    let length = behaviour1.len().max(behaviour2.len());
+    let mut total = 0.0;
+    for i in 0..length {
+        let x = behaviour1.get(i).cloned().unwrap_or(0.0);
+        let y = behaviour2.get(i).cloned().unwrap_or(0.0);
+        total += (x - y) * (x - y);
+    }
+    total.sqrt()
+}
+
+pub fn novelty_scores(population: &[Board], sample: &[usize], archive: &Archive) -> Vec<f64> {
+    // novelty of each sampled board compared against every
+    // other board in the population and the archive
+    let behaviours: Vec<Vec<f64>> = population.iter().map(behaviour).collect();
+    let mut scores = Vec::with_capacity(sample.len());
+    for i in sample {
+        let others: Vec<Vec<f64>> = behaviours
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| j != i)
+            .map(|(_, b)| b.clone())
+            .collect();
+        scores.push(archive.novelty(&behaviours[*i], owner(&population[*i]), &others));
+    }
+    scores
+}
+
+pub fn update_archive(archive: &mut Archive, population: &[Board], indices: &[usize]) {
+    // offer newly made boards to the archive, which keeps
+    // them if their behaviour is novel enough
+    let novelty_values = novelty_scores(population, indices, archive);
+    for (i, novelty_value) in indices.iter().zip(novelty_values) {
+        archive.consider(behaviour(&population[*i]), owner(&population[*i]), novelty_value);
+    }
+}
+
+fn owner(board: &Board) -> usize {
+    board.automata.as_ref().expect("Board is missing automata.").id
+}
+
+pub fn scores(population: &[Board], sample: &[usize], archive: &Archive, objective: Objective) -> Vec<f64> {
+    // the values used to rank the sampled boards when
+    // picking parents and boards to replace
+    let fitness: Vec<f64> = sample.iter().map(|i| population[*i].fitness as f64).collect();
+    match objective {
+        Objective::Fitness => fitness,
+        Objective::Novelty => novelty_scores(population, sample, archive),
+        Objective::Blend(weight) => {
+            assert!((0.0..=1.0).contains(&weight));
+            // fitness and novelty are on different scales, so
+            // both are normalised over the sample first
+            let fitness = normalise(&fitness);
+            let novelty = normalise(&novelty_scores(population, sample, archive));
+            fitness
+                .iter()
+                .zip(novelty.iter())
+                .map(|(f, n)| weight * n + (1.0 - weight) * f)
+                .collect()
+        }
+    }
+}
+
+fn normalise(values: &[f64]) -> Vec<f64> {
+    // scale values to be between 0 and 1; if all values
+    // are the same they are all set to 0
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max <= min {
+        return vec![0.0; values.len()];
+    }
+    values.iter().map(|v| (v - min) / (max - min)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_distance() {
+        assert_eq!(distance(&[3.0, 4.0], &[]), 5.0);
+        assert_eq!(distance(&[1.0, 2.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(distance(&[1.0], &[1.0, 0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn nearest_neighbours() {
+        let mut archive = Archive::new(2, 0.0, 10);
+        let others = vec![vec![1.0], vec![2.0], vec![10.0]];
+        assert_eq!(archive.novelty(&[0.0], 1, &others), 1.5);
+        archive.consider(vec![0.0], 2, 1.0);
+        assert_eq!(archive.novelty(&[0.0], 1, &others), 0.5);
+        // a board is not its own neighbour
+        assert_eq!(archive.novelty(&[0.0], 2, &others), 1.5);
+        // k larger than the number of neighbours
+        let archive = Archive::new(5, 0.0, 10);
+        assert_eq!(archive.novelty(&[0.0], 1, &[vec![4.0]]), 4.0);
+        assert_eq!(archive.novelty(&[0.0], 1, &[]), 0.0);
+    }
+
+    #[test]
+    fn archive_capacity() {
+        let mut archive = Archive::new(1, 1.0, 2);
+        assert!(!archive.consider(vec![1.0], 1, 0.5));
+        assert!(archive.consider(vec![2.0], 2, 1.5));
+        assert!(archive.consider(vec![3.0], 3, 1.5));
+        assert!(archive.consider(vec![4.0], 4, 1.5));
+        assert_eq!(archive.behaviours, vec![vec![3.0], vec![4.0]]);
+        assert_eq!(archive.owners, vec![3, 4]);
+    }
+
+    #[test]
+    fn normalised_values() {
+        assert_eq!(normalise(&[2.0, 4.0, 3.0]), vec![0.0, 1.0, 0.5]);
+        assert_eq!(normalise(&[7.0, 7.0]), vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use crate::colour::clamp_unit;
+use crate::colour::Colour as Colour;
+
+// colour of state 0 (dead cells) unless another is given
+pub const BACKGROUND: Colour = Colour { r: 255, g: 255, b: 255, a: 1.0 };
+
+// stops along the viridis colour map, from dark purple to yellow
+const VIRIDIS: [(u8, u8, u8); 5] = [
+    (0x44, 0x01, 0x54),
+    (0x3b, 0x52, 0x8b),
+    (0x21, 0x91, 0x8c),
+    (0x5e, 0xc9, 0x62),
+    (0xfd, 0xe7, 0x25),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Palette {
+    // light grey for state 1 down to black for the last state
+    Greyscale,
+    // evenly spaced along a viridis-like gradient
+    Viridis,
+    // evenly spaced hues with the same saturation and lightness
+    // (values outside 0 to 1 are clamped)
+    Hues { saturation: f64, lightness: f64 },
+    // colours for the live states, repeated if there are
+    // more states than colours (an empty list falls back
+    // to greyscale)
+    Custom(Vec<Colour>),
+}
+
+impl Palette {
+    pub fn colours(&self, num_states: usize) -> Vec<Colour> {
+        self.colours_with_background(num_states, BACKGROUND)
+    }
+
+    pub fn colours_with_background(&self, num_states: usize, background: Colour) -> Vec<Colour> {
+        // one colour per state; the same palette and number of
+        // states always give the same colours
+        if num_states == 0 {
+            return Vec::new();
+        }
+        let num_live = num_states - 1;
+        let mut colours = Vec::with_capacity(num_states);
+        colours.push(background);
+        for i in 0..num_live {
+            // position of the state along the palette, from 0 to 1
+            let t = if num_live == 1 { 1.0 } else { i as f64 / (num_live - 1) as f64 };
+            let colour = match self {
+                Palette::Viridis => gradient(&VIRIDIS, t),
+                Palette::Hues { saturation, lightness } => {
+                    let hue = 360.0 * i as f64 / num_live as f64;
+                    // the hue is always finite and the rest are in
+                    // range, so this cannot fail
+                    Colour::from_hsl(hue, clamp_unit(*saturation), clamp_unit(*lightness), 1.0)
+                        .unwrap_or_else(|_| grey(t))
+                }
+                Palette::Custom(list) if !list.is_empty() => list[i % list.len()].clone(),
+                Palette::Greyscale | Palette::Custom(_) => grey(t),
+            };
+            colours.push(colour);
+        }
+        colours
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Palette, String> {
+        // the named palettes; hues are fairly bright
+        match s.trim().to_lowercase().as_str() {
+            "greyscale" | "grayscale" => Ok(Palette::Greyscale),
+            "viridis" => Ok(Palette::Viridis),
+            "hues" => Ok(Palette::Hues { saturation: 0.7, lightness: 0.5 }),
+            name => Err(format!("unknown palette '{}'", name)),
+        }
+    }
+}
+
+fn grey(t: f64) -> Colour {
+    // light grey at 0 down to black at 1
+    let level = (200.0 * (1.0 - t)).round() as u8;
+    Colour::new(level, level, level, 1.0)
+}
+
+fn gradient(stops: &[(u8, u8, u8)], t: f64) -> Colour {
+    // linear interpolation between the two stops either side of t
+    let position = t * (stops.len() - 1) as f64;
+    let i = (position.floor() as usize).min(stops.len() - 2);
+    let (r1, g1, b1) = stops[i];
+    let (r2, g2, b2) = stops[i + 1];
+    Colour::new(r1, g1, b1, 1.0).interpolate(&Colour::new(r2, g2, b2, 1.0), position - i as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn background_and_length() {
+        let palettes = [
+            Palette::Greyscale,
+            Palette::Viridis,
+            Palette::Hues { saturation: 0.7, lightness: 0.5 },
+            Palette::Custom(vec![Colour::new(1, 2, 3, 1.0)]),
+        ];
+        for palette in palettes.iter() {
+            for num_states in 1..6 {
+                let colours = palette.colours(num_states);
+                assert_eq!(colours.len(), num_states);
+                assert_eq!(colours[0], BACKGROUND);
+                // deterministic
+                assert_eq!(colours, palette.colours(num_states));
+            }
+        }
+        let black = Colour::new(0, 0, 0, 1.0);
+        assert_eq!(Palette::Viridis.colours_with_background(3, black.clone())[0], black);
+    }
+
+    #[test]
+    fn palette_ends() {
+        let greys = Palette::Greyscale.colours(4);
+        assert_eq!(greys[1], Colour::new(200, 200, 200, 1.0));
+        assert_eq!(greys[3], Colour::new(0, 0, 0, 1.0));
+        let viridis = Palette::Viridis.colours(3);
+        assert_eq!(viridis[1], Colour::new(0x44, 0x01, 0x54, 1.0));
+        assert_eq!(viridis[2], Colour::new(0xfd, 0xe7, 0x25, 1.0));
+        let hues = Palette::Hues { saturation: 1.0, lightness: 0.5 }.colours(4);
+        assert_eq!(hues[1], Colour::new(255, 0, 0, 1.0));
+        assert_eq!(hues[2], Colour::new(0, 255, 0, 1.0));
+        let custom = Palette::Custom(vec![Colour::new(1, 2, 3, 1.0), Colour::new(4, 5, 6, 1.0)]).colours(4);
+        assert_eq!(custom[3], custom[1]);
+    }
+
+    #[test]
+    fn bad_palettes() {
+        // out of range values are clamped rather than panicking
+        let hues = Palette::Hues { saturation: 2.0, lightness: f64::NAN }.colours(3);
+        assert_eq!(hues, Palette::Hues { saturation: 1.0, lightness: 0.0 }.colours(3));
+        assert_eq!(Palette::Custom(vec![]).colours(4), Palette::Greyscale.colours(4));
+        assert!(Palette::Viridis.colours(0).is_empty());
+    }
+
+    #[test]
+    fn named_palettes() {
+        assert_eq!("Viridis".parse(), Ok(Palette::Viridis));
+        assert_eq!("greyscale".parse(), Ok(Palette::Greyscale));
+        assert!("rainbow".parse::<Palette>().is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::board::{evaluate, Board};
+use crate::experiment::{Config, Experiment};
+use crate::palette::Palette as Palette;
+use crate::svg::{board_to_svg, SvgOptions};
+
+const PLOT_WIDTH: f64 = 600.0;
+const PLOT_HEIGHT: f64 = 240.0;
+const MARGIN: f64 = 40.0;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ccc; padding: 2px 8px; text-align: left; }
+.rules { font-family: monospace; word-break: break-all; }
+.automata { display: inline-block; margin: 0 1em 1em 0; vertical-align: top; }";
+
+pub fn report(experiment: &Experiment, palette: &Palette, num_automata: usize) -> String {
+    // a page that needs nothing else to be viewed: the
+    // configuration, plots of the run and the best automata
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Apoptotic cellular automata</title>\n");
+    html.push_str(&format!("<style>\n{}\n</style>\n</head>\n<body>\n", STYLE));
+    html.push_str("<h1>Apoptotic cellular automata</h1>\n");
+
+    html.push_str("<h2>Configuration</h2>\n<table>\n");
+    for (name, value) in config_rows(&experiment.config) {
+        html.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", name, escape(&value)));
+    }
+    html.push_str("</table>\n");
+    html.push_str(&format!("<h2>Results</h2>\n<pre>{}</pre>\n", escape(&experiment.statistics().to_string())));
+
+    html.push_str("<h2>Convergence</h2>\n");
+    let history = &experiment.history;
+    let mevs: Vec<f64> = history.iter().map(|e| e.mev as f64).collect();
+    html.push_str(&line_plot(
+        "fitness",
+        &mevs,
+        &[
+            ("best", "#1b9e77", history.iter().map(|e| e.best_fitness as f64).collect()),
+            ("mean", "#7570b3", history.iter().map(|e| e.mean_fitness).collect()),
+            ("worst", "#d95f02", history.iter().map(|e| e.worst_fitness as f64).collect()),
+        ],
+    ));
+    html.push_str(&line_plot(
+        "diversity",
+        &mevs,
+        &[
+            ("mean distance", "#1b9e77", history.iter().map(|e| e.mean_distance).collect()),
+            ("unique rules", "#7570b3", history.iter().map(|e| e.unique_rules as f64).collect()),
+        ],
+    ));
+
+    html.push_str("<h2>Top automata</h2>\n");
+    for (rank, board) in top_boards(experiment, num_automata).iter().enumerate() {
+        let automata = board.automata.as_ref().expect("Board is missing automata.");
+        let rules: Vec<String> = automata.rule_string.iter().map(|c| c.to_string()).collect();
+        html.push_str("<div class=\"automata\">\n");
+        html.push_str(&format!("<h3>{}. fitness {}</h3>\n", rank + 1, board.fitness));
+        let options = SvgOptions {
+            scale: 4,
+            ..SvgOptions::default()
+        };
+        html.push_str(&board_to_svg(&peak(board, &experiment.config), &board.colours(palette), &options));
+        html.push_str(&format!("<p class=\"rules\">{}</p>\n</div>\n", rules.join("")));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn write_report(experiment: &Experiment, output_file: &str, palette: &Palette, num_automata: usize) -> std::io::Result<()> {
+    std::fs::write(output_file, report(experiment, palette, num_automata))?;
+    Ok(())
+}
+
+fn config_rows(config: &Config) -> Vec<(&'static str, String)> {
+    vec![
+        ("mating events", config.num_mevs.to_string()),
+        ("population size", config.population_size.to_string()),
+        ("states", config.num_states.to_string()),
+        ("tournament size", config.tournament_size.to_string()),
+        ("neighbourhood size", config.neighbourhood_size.to_string()),
+        ("dimensions", if config.is_2d { "2D" } else { "1D" }.to_string()),
+        ("board", format!("{} x {}", config.width, config.height)),
+        ("iterations", config.num_iters.to_string()),
+        ("start population", format!("{:?}", config.start_population)),
+        ("crossover", format!("{:?}", config.crossover)),
+        ("max mutations", config.max_mutations.to_string()),
+        ("mutation schedule", format!("{:?}", config.mutation_schedule)),
+        ("mutation operators", format!("{:?}", config.mutation_operators)),
+        ("scheme", format!("{:?}", config.scheme)),
+        ("selection", format!("{:?}", config.parent_selection())),
+        ("replacement", format!("{:?}", config.replacement)),
+        ("duplicates", format!("{:?}", config.duplicates)),
+        ("objective", format!("{:?}", config.objective)),
+        ("locked positions", format!("{:?}", config.locked_positions)),
+        ("cache size", config.cache_size.to_string()),
+        ("novelty neighbours", config.novelty_neighbours.to_string()),
+        ("novelty threshold", config.novelty_threshold.to_string()),
+        ("archive size", config.archive_size.to_string()),
+        ("hall of fame size", config.hall_of_fame_size.to_string()),
+        ("target fitness", optional(config.target_fitness)),
+        ("patience", optional(config.patience)),
+        ("min diversity", optional(config.min_diversity)),
+        ("time budget", optional(config.time_budget.map(|t| format!("{:?}", t)))),
+    ]
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or("none".to_string(), |v| v.to_string())
+}
+
+fn top_boards(experiment: &Experiment, num_automata: usize) -> Vec<Board> {
+    // the hall of fame if anything made it in, otherwise
+    // the best of the final population
+    let config = &experiment.config;
+    if !experiment.hall_of_fame.is_empty() {
+        return experiment
+            .hall_of_fame
+            .entries
+            .iter()
+            .take(num_automata)
+            .map(|e| {
+                let automata = e.automata.clone();
+                evaluate(automata, config.width, config.height, &config.start_population, config.num_iters)
+            })
+            .collect();
+    }
+    let mut boards = experiment.boards.clone();
+    boards.sort_by_key(|b| std::cmp::Reverse(b.fitness));
+    boards.truncate(num_automata);
+    boards
+}
+
+fn peak(board: &Board, config: &Config) -> Board {
+    // a 1D board already shows its whole history; an apoptotic
+    // 2D board ends up empty, so it is shown at the layer with
+    // the most live cells instead
+    let automata = board.automata.clone().expect("Board is missing automata.");
+    if !automata.is_2d {
+        return board.clone();
+    }
+    let mut peak = Board::initialise(config.width, config.height);
+    let layers = peak.run_recorded(automata, &config.start_population, config.num_iters);
+    let live = |layer: &Vec<Vec<usize>>| layer.iter().flatten().filter(|c| **c != 0).count();
+    if let Some(layer) = layers.iter().max_by_key(|layer| live(layer)) {
+        peak.cell_states = layer.clone();
+    }
+    peak
+}
+
+fn line_plot(title: &str, xs: &[f64], series: &[(&str, &str, Vec<f64>)]) -> String {
+    // one polyline per series over the same x values, scaled
+    // to fit the plot, with the ranges written on the axes
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        PLOT_WIDTH + 2.0 * MARGIN,
+        PLOT_HEIGHT + 2.0 * MARGIN
+    );
+    svg.push_str(&format!("<text x=\"{}\" y=\"20\" font-size=\"14\">{}</text>\n", MARGIN, title));
+    let ys = series.iter().flat_map(|(_, _, ys)| ys.iter().cloned());
+    let (y_min, y_max) = ys.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), y| (lo.min(y), hi.max(y)));
+    if xs.is_empty() || !y_min.is_finite() {
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\">no data</text>\n</svg>\n", MARGIN, MARGIN + 20.0));
+        return svg;
+    }
+    let x_min = xs[0];
+    let x_max = xs[xs.len() - 1];
+    let x_range = if x_max > x_min { x_max - x_min } else { 1.0 };
+    let y_range = if y_max > y_min { y_max - y_min } else { 1.0 };
+    let px = |x: f64| MARGIN + (x - x_min) / x_range * PLOT_WIDTH;
+    let py = |y: f64| MARGIN + PLOT_HEIGHT - (y - y_min) / y_range * PLOT_HEIGHT;
+
+    let bottom = MARGIN + PLOT_HEIGHT;
+    svg.push_str(&format!(
+        "<path d=\"M{m} {m}V{b}H{r}\" stroke=\"#000\" fill=\"none\"/>\n",
+        m = MARGIN,
+        b = bottom,
+        r = MARGIN + PLOT_WIDTH
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"end\">{}</text>\n",
+        MARGIN - 4.0, bottom, y_min
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"end\">{}</text>\n",
+        MARGIN - 4.0, MARGIN + 10.0, y_max
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"11\">{}</text>\n",
+        MARGIN, bottom + 14.0, x_min
+    ));
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"end\">{}</text>\n",
+        MARGIN + PLOT_WIDTH, bottom + 14.0, x_max
+    ));
+
+    for (i, (name, colour, ys)) in series.iter().enumerate() {
+        let points: Vec<String> = xs.iter().zip(ys).map(|(x, y)| format!("{:.1},{:.1}", px(*x), py(*y))).collect();
+        svg.push_str(&format!(
+            "<polyline points=\"{}\" stroke=\"{}\" fill=\"none\"/>\n",
+            points.join(" "),
+            colour
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"11\" fill=\"{}\">{}</text>\n",
+            MARGIN + 100.0 * i as f64,
+            bottom + 30.0,
+            colour,
+            name
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plots() {
+        let plot = line_plot("fitness", &[1.0, 2.0, 3.0], &[("best", "#000", vec![0.0, 5.0, 10.0])]);
+        assert!(plot.contains("<polyline points=\"40.0,280.0 340.0,160.0 640.0,40.0\""));
+        assert!(plot.contains(">best</text>"));
+        let empty = line_plot("fitness", &[], &[("best", "#000", vec![])]);
+        assert!(empty.contains("no data"));
+        assert!(!empty.contains("<polyline"));
+    }
+
+    #[test]
+    fn every_setting() {
+        let config = Config {
+            patience: Some(7),
+            ..Config::default()
+        };
+        let rows = config_rows(&config);
+        // one row per field of the config, with the width and
+        // height sharing a row
+        assert_eq!(rows.len(), 28);
+        assert!(rows.contains(&("patience", "7".to_string())));
+        assert!(rows.contains(&("time budget", "none".to_string())));
+    }
+
+    #[test]
+    fn escaped() {
+        assert_eq!(escape("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+    }
+}
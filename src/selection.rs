@@ -0,0 +1,300 @@
+extern crate rand;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    // fitness proportionate (roulette wheel) selection
+    Roulette,
+    // linear ranking: the worst has weight 1, the best has
+    // weight n (ties are broken by position)
+    Rank,
+    // uniform selection from the best fraction (between 0 and 1)
+    Truncation(f64),
+    // weights of exp(score / temperature); low temperatures
+    // give more selection pressure
+    Boltzmann(f64),
+    // the best of k individuals sampled without replacement
+    Tournament(usize),
+}
+
+impl Selection {
+    pub fn select<R: Rng>(&self, scores: &[f64], rng: &mut R) -> usize {
+        // pick one index from scores
+        self.select_excluding(scores, None, rng)
+    }
+
+    pub fn select_pair<R: Rng>(&self, scores: &[f64], rng: &mut R) -> (usize, usize) {
+        // pick two different indices from scores; for tournaments
+        // of two or more these are the best two of the sample (so
+        // a tournament with k equal to the number of scores always
+        // picks the two best), while a tournament of one is two
+        // random picks like any other operator
+        assert!(1 < scores.len());
+        if let Selection::Tournament(k) = *self {
+            if 1 < k {
+                let mut sample: Vec<usize> = (0..scores.len()).collect();
+                sample.shuffle(rng);
+                sample.truncate(k);
+                let sample_scores: Vec<f64> = sample.iter().map(|i| scores[*i]).collect();
+                let (max1, max2) = max_two_indices(&sample_scores);
+                return (sample[max1], sample[max2]);
+            }
+        }
+        let first = self.select_excluding(scores, None, rng);
+        let second = self.select_excluding(scores, Some(first), rng);
+        (first, second)
+    }
+
+    fn select_excluding<R: Rng>(&self, scores: &[f64], exclude: Option<usize>, rng: &mut R) -> usize {
+        assert!(!scores.is_empty());
+        let candidates: Vec<usize> = (0..scores.len()).filter(|i| Some(*i) != exclude).collect();
+        assert!(!candidates.is_empty());
+        let weights: Vec<f64> = match self {
+            Selection::Roulette => {
+                // negative scores cannot be proportioned, so
+                // they are treated as zero
+                candidates.iter().map(|i| scores[*i].max(0.0)).collect()
+            }
+            Selection::Rank => {
+                let order = ranks(scores);
+                candidates.iter().map(|i| (order[*i] + 1) as f64).collect()
+            }
+            Selection::Truncation(fraction) => {
+                assert!(0.0 < *fraction && *fraction <= 1.0);
+                let order = ranks(scores);
+                let cutoff = ((scores.len() as f64 * fraction).ceil() as usize).max(1);
+                // the excluded index might be in the top fraction,
+                // in which case the cut off is widened by one to
+                // keep the same number of candidates
+                let cutoff = match exclude {
+                    Some(i) if scores.len() - cutoff <= order[i] => (cutoff + 1).min(scores.len()),
+                    _ => cutoff,
+                };
+                candidates
+                    .iter()
+                    .map(|i| if scores.len() - cutoff <= order[*i] { 1.0 } else { 0.0 })
+                    .collect()
+            }
+            Selection::Boltzmann(temperature) => {
+                assert!(0.0 < *temperature);
+                // shifting by the maximum candidate avoids overflow
+                // (and every weight underflowing to zero) and does
+                // not change the probabilities
+                let max = candidates.iter().map(|i| scores[*i]).fold(f64::NEG_INFINITY, f64::max);
+                candidates.iter().map(|i| ((scores[*i] - max) / temperature).exp()).collect()
+            }
+            Selection::Tournament(k) => {
+                assert!(0 < *k);
+                let mut sample = candidates.clone();
+                sample.shuffle(rng);
+                sample.truncate(*k);
+                let mut best = sample[0];
+                for i in &sample[1..] {
+                    if scores[best] < scores[*i] {
+                        best = *i;
+                    }
+                }
+                return best;
+            }
+        };
+        candidates[spin(&weights, rng)]
+    }
+}
+
+fn ranks(scores: &[f64]) -> Vec<usize> {
+    // position of each score when sorted from worst (0) to best
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|a, b| scores[*a].partial_cmp(&scores[*b]).unwrap());
+    let mut ranks = vec![0; scores.len()];
+    for (rank, i) in order.iter().enumerate() {
+        ranks[*i] = rank;
+    }
+    ranks
+}
+
+fn max_two_indices<T: PartialOrd>(s: &[T]) -> (usize, usize) {
+    // given a borrowed vector, pick the indices where
+    // the largest two values reside and return those
+    // indices as a tuple (max1, max2), where
+    // s[max2] < s[max1]
+    assert!(1 < s.len());
+    let mut max1: usize;
+    let mut max2: usize;
+    if s[0] < s[1] {
+        max1 = 1;
+        max2 = 0;
+    } else {
+        max1 = 0;
+        max2 = 1;
+    }
+    for i in 2..s.len() {
+        if s[max1] < s[i] {
+            max2 = max1;
+            max1 = i;
+        } else if s[max2] < s[i] {
+            max2 = i;
+        }
+    }
+    (max1, max2)
+}
+
+fn spin<R: Rng>(weights: &[f64], rng: &mut R) -> usize {
+    // roulette wheel over the weights; if all the weights
+    // are zero then every index is equally likely
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return rng.gen_range(0, weights.len());
+    }
+    let mut target = rng.gen::<f64>() * total;
+    for (i, w) in weights.iter().enumerate() {
+        if target < *w {
+            return i;
+        }
+        target -= w;
+    }
+    // rounding errors can leave a tiny remainder
+    weights.iter().rposition(|w| 0.0 < *w).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAWS: usize = 20000;
+
+    fn frequencies(selection: Selection, scores: &[f64]) -> Vec<f64> {
+        let mut rng = rand::thread_rng();
+        let mut counts = vec![0; scores.len()];
+        for _ in 0..DRAWS {
+            counts[selection.select(scores, &mut rng)] += 1;
+        }
+        counts.iter().map(|c| *c as f64 / DRAWS as f64).collect()
+    }
+
+    fn close(observed: f64, expected: f64) -> bool {
+        // allow for sampling noise (several standard errors)
+        (observed - expected).abs() < 0.02
+    }
+
+    #[test]
+    fn roulette_is_proportionate() {
+        let f = frequencies(Selection::Roulette, &[1.0, 2.0, 3.0, 4.0]);
+        for (i, expected) in [0.1, 0.2, 0.3, 0.4].iter().enumerate() {
+            assert!(close(f[i], *expected), "{:?}", f);
+        }
+        // all zero scores fall back to uniform selection
+        let f = frequencies(Selection::Roulette, &[0.0, 0.0]);
+        assert!(close(f[0], 0.5), "{:?}", f);
+    }
+
+    #[test]
+    fn rank_ignores_scale() {
+        let f = frequencies(Selection::Rank, &[1000.0, 0.0, 1.0]);
+        // ranks are 3, 1, 2 out of a total of 6
+        for (i, expected) in [0.5, 1.0 / 6.0, 1.0 / 3.0].iter().enumerate() {
+            assert!(close(f[i], *expected), "{:?}", f);
+        }
+    }
+
+    #[test]
+    fn truncation_only_picks_the_best() {
+        let f = frequencies(Selection::Truncation(0.5), &[5.0, 1.0, 4.0, 2.0]);
+        assert_eq!(f[1], 0.0);
+        assert_eq!(f[3], 0.0);
+        assert!(close(f[0], 0.5), "{:?}", f);
+        // without the best, the next two best share the picks
+        let mut rng = rand::thread_rng();
+        let mut counts = [0; 4];
+        for _ in 0..DRAWS {
+            counts[Selection::Truncation(0.5).select_excluding(&[5.0, 1.0, 4.0, 2.0], Some(0), &mut rng)] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        assert_eq!(counts[1], 0);
+        assert!(close(counts[3] as f64 / DRAWS as f64, 0.5), "{:?}", counts);
+    }
+
+    #[test]
+    fn boltzmann_temperature() {
+        let scores = [0.0, 1.0];
+        // at temperature 1 the odds are e to 1
+        let f = frequencies(Selection::Boltzmann(1.0), &scores);
+        let e = 1.0f64.exp();
+        assert!(close(f[1], e / (1.0 + e)), "{:?}", f);
+        // colder means more pressure, hotter means less
+        let cold = frequencies(Selection::Boltzmann(0.1), &scores);
+        let hot = frequencies(Selection::Boltzmann(100.0), &scores);
+        assert!(0.99 < cold[1]);
+        assert!(close(hot[1], 0.5), "{:?}", hot);
+    }
+
+    #[test]
+    fn tournament_pressure() {
+        let scores = [1.0, 2.0, 3.0, 4.0];
+        // the worst can only win a tournament of one, and the
+        // best wins with probability 1 - (3/4 * 2/3) = 1/2 for k = 2
+        let f = frequencies(Selection::Tournament(2), &scores);
+        assert_eq!(f[0], 0.0);
+        assert!(close(f[3], 0.5), "{:?}", f);
+        let f = frequencies(Selection::Tournament(4), &scores);
+        assert_eq!(f[3], 1.0);
+        let f = frequencies(Selection::Tournament(1), &scores);
+        assert!(close(f[0], 0.25), "{:?}", f);
+    }
+
+    #[test]
+    fn test_maximum() {
+        let mut result: (usize, usize);
+        let v0 = vec![0, 4, 2, 5, 3];
+        result = max_two_indices(&v0);
+        assert_eq!(result, (3, 1));
+
+        let v1 = vec![0, 0, 0, 0, 0];
+        result = max_two_indices(&v1);
+        assert_eq!(result, (0, 1));
+
+        let v2 = vec![0, 0, 0, 0, 1];
+        result = max_two_indices(&v2);
+        assert_eq!(result, (4, 0));
+
+        let v3 = vec![0, 0, 435, 22, 7];
+        result = max_two_indices(&v3);
+        assert_eq!(result, (2, 3));
+
+        let v4 = vec![5454, 33, 435, 22, 7];
+        result = max_two_indices(&v4);
+        assert_eq!(result, (0, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed")]
+    fn maximum_2_of_1() {
+        let v0 = vec![0];
+        let result = max_two_indices(&v0);
+        assert!(false, result);
+    }
+
+    #[test]
+    fn pairs_are_distinct() {
+        let mut rng = rand::thread_rng();
+        let scores = [0.0, 0.0, 10.0];
+        for selection in &[
+            Selection::Roulette,
+            Selection::Rank,
+            Selection::Truncation(0.1),
+            Selection::Boltzmann(0.01),
+            Selection::Tournament(2),
+            Selection::Tournament(1),
+        ] {
+            for _ in 0..100 {
+                let (first, second) = selection.select_pair(&scores, &mut rng);
+                assert_ne!(first, second);
+            }
+        }
+        // a tournament over everyone gives the best two, and so
+        // does a cold enough Boltzmann selection
+        assert_eq!(Selection::Tournament(3).select_pair(&[3.0, 1.0, 2.0], &mut rng), (0, 2));
+        assert_eq!(Selection::Boltzmann(0.001).select_pair(&[3.0, 1.0, 2.0], &mut rng), (0, 2));
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::Write;
+
+use crate::board::Board as Board;
+use crate::diversity::Diversity;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    // comma separated values with a header row; the replaced
+    // indices are separated by semicolons
+    Csv,
+    // one JSON object per line
+    JsonLines,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventStatistics {
+    // mating event (or generation) number, starting at 1
+    pub mev: usize,
+    pub best_fitness: usize,
+    pub mean_fitness: f64,
+    pub median_fitness: f64,
+    pub worst_fitness: usize,
+    pub num_apoptotic: usize,
+    pub mean_distance: f64,
+    pub unique_rules: usize,
+    pub mean_entropy: f64,
+    // indices of the boards replaced during the event
+    pub replaced: Vec<usize>,
+}
+
+impl EventStatistics {
+    pub fn new(mev: usize, boards: &[Board], diversity: &Diversity, replaced: Vec<usize>) -> EventStatistics {
+        assert!(!boards.is_empty());
+        let mut fitness_values: Vec<usize> = boards.iter().map(|b| b.fitness).collect();
+        fitness_values.sort();
+        let n = fitness_values.len();
+        let median_fitness = if n % 2 == 1 {
+            fitness_values[n / 2] as f64
+        } else {
+            (fitness_values[n / 2 - 1] + fitness_values[n / 2]) as f64 / 2.0
+        };
+        EventStatistics {
+            mev,
+            best_fitness: fitness_values[n - 1],
+            mean_fitness: fitness_values.iter().sum::<usize>() as f64 / n as f64,
+            median_fitness,
+            worst_fitness: fitness_values[0],
+            num_apoptotic: boards.iter().filter(|b| b.apoptotic).count(),
+            mean_distance: diversity.mean_distance,
+            unique_rules: diversity.unique_rules,
+            mean_entropy: diversity.mean_entropy(),
+            replaced,
+        }
+    }
+
+    pub fn csv_header() -> &'static str {
+        "mev,best_fitness,mean_fitness,median_fitness,worst_fitness,num_apoptotic,mean_distance,unique_rules,mean_entropy,replaced"
+    }
+
+    pub fn to_csv(&self) -> String {
+        let replaced: Vec<String> = self.replaced.iter().map(|i| i.to_string()).collect();
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.mev,
+            self.best_fitness,
+            self.mean_fitness,
+            self.median_fitness,
+            self.worst_fitness,
+            self.num_apoptotic,
+            self.mean_distance,
+            self.unique_rules,
+            self.mean_entropy,
+            replaced.join(";")
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        let replaced: Vec<String> = self.replaced.iter().map(|i| i.to_string()).collect();
+        format!(
+            "{{\"mev\":{},\"best_fitness\":{},\"mean_fitness\":{},\"median_fitness\":{},\"worst_fitness\":{},\"num_apoptotic\":{},\"mean_distance\":{},\"unique_rules\":{},\"mean_entropy\":{},\"replaced\":[{}]}}",
+            self.mev,
+            self.best_fitness,
+            self.mean_fitness,
+            self.median_fitness,
+            self.worst_fitness,
+            self.num_apoptotic,
+            self.mean_distance,
+            self.unique_rules,
+            self.mean_entropy,
+            replaced.join(",")
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct StatisticsWriter<W: Write> {
+    // writes statistics one event at a time, so a long run
+    // can be watched (or plotted) while it is going
+    writer: W,
+    format: Format,
+    header_written: bool,
+}
+
+impl<W: Write> StatisticsWriter<W> {
+    pub fn new(writer: W, format: Format) -> StatisticsWriter<W> {
+        StatisticsWriter {
+            writer,
+            format,
+            header_written: false,
+        }
+    }
+
+    pub fn write(&mut self, event: &EventStatistics) -> std::io::Result<()> {
+        match self.format {
+            Format::Csv => {
+                if !self.header_written {
+                    writeln!(self.writer, "{}", EventStatistics::csv_header())?;
+                    self.header_written = true;
+                }
+                writeln!(self.writer, "{}", event.to_csv())
+            }
+            Format::JsonLines => writeln!(self.writer, "{}", event.to_json()),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub fn write_statistics(events: &[EventStatistics], output_file: &str, format: Format) -> std::io::Result<()> {
+    let file = std::fs::File::create(output_file)?;
+    let mut writer = StatisticsWriter::new(std::io::BufWriter::new(file), format);
+    for event in events {
+        writer.write(event)?;
+    }
+    writer.into_inner().flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> EventStatistics {
+        EventStatistics {
+            mev: 3,
+            best_fitness: 10,
+            mean_fitness: 4.5,
+            median_fitness: 4.0,
+            worst_fitness: 0,
+            num_apoptotic: 2,
+            mean_distance: 1.25,
+            unique_rules: 4,
+            mean_entropy: 0.5,
+            replaced: vec![1, 7],
+        }
+    }
+
+    #[test]
+    fn csv_rows() {
+        let mut writer = StatisticsWriter::new(Vec::new(), Format::Csv);
+        writer.write(&event()).unwrap();
+        writer.write(&event()).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], EventStatistics::csv_header());
+        assert_eq!(lines[1], "3,10,4.5,4,0,2,1.25,4,0.5,1;7");
+        assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+    }
+
+    #[test]
+    fn json_lines() {
+        let mut writer = StatisticsWriter::new(Vec::new(), Format::JsonLines);
+        writer.write(&event()).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            "{\"mev\":3,\"best_fitness\":10,\"mean_fitness\":4.5,\"median_fitness\":4,\"worst_fitness\":0,\"num_apoptotic\":2,\"mean_distance\":1.25,\"unique_rules\":4,\"mean_entropy\":0.5,\"replaced\":[1,7]}\n"
+        );
+    }
+}
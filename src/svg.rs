@@ -0,0 +1,172 @@
+use crate::board::Board as Board;
+use crate::colour::Colour as Colour;
+
+// room above the board for the title and to the
+// right of it for the legend
+const TITLE_HEIGHT: usize = 24;
+const LEGEND_WIDTH: usize = 60;
+const LEGEND_ROW: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    // width and height of each cell
+    pub scale: usize,
+    // lines between the cells
+    pub grid: bool,
+    // a swatch and number for each state
+    pub legend: bool,
+    // the rule string and fitness above the board
+    pub title: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions {
+            scale: 10,
+            grid: false,
+            legend: false,
+            title: false,
+        }
+    }
+}
+
+pub fn board_to_svg(board: &Board, colours: &[Colour], options: &SvgOptions) -> String {
+    // state 0 is drawn once as the background, then each run of
+    // live cells with the same state along a row is one rect
+    assert!(0 < options.scale);
+    assert!(!colours.is_empty());
+    let scale = options.scale;
+    let board_width = board.width() * scale;
+    let board_height = board.cell_states.len() * scale;
+    let top = if options.title { TITLE_HEIGHT } else { 0 };
+    let width = board_width + if options.legend { LEGEND_WIDTH } else { 0 };
+    let height = top + board_height.max(if options.legend { colours.len() * LEGEND_ROW } else { 0 });
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+    if options.title {
+        svg.push_str(&format!(
+            "<text x=\"0\" y=\"{}\" font-family=\"monospace\" font-size=\"14\">{}</text>\n",
+            TITLE_HEIGHT - 8,
+            title(board)
+        ));
+    }
+    svg.push_str(&format!("<g transform=\"translate(0 {})\">\n", top));
+    svg.push_str(&rect(0, 0, board_width, board_height, &colours[0]));
+    for (i, row) in board.cell_states.iter().enumerate() {
+        let mut start = 0;
+        while start < row.len() {
+            let state = row[start];
+            let mut end = start + 1;
+            while end < row.len() && row[end] == state {
+                end += 1;
+            }
+            if state != 0 {
+                let c = colours.get(state).expect("Not enough colours for all cell states");
+                svg.push_str(&rect(start * scale, i * scale, (end - start) * scale, scale, c));
+            }
+            start = end;
+        }
+    }
+    if options.grid {
+        let mut path = String::new();
+        for x in 1..board.width() {
+            path.push_str(&format!("M{} 0V{}", x * scale, board_height));
+        }
+        for y in 1..board.cell_states.len() {
+            path.push_str(&format!("M0 {}H{}", y * scale, board_width));
+        }
+        svg.push_str(&format!(
+            "<path d=\"{}\" stroke=\"#808080\" stroke-width=\"0.5\" fill=\"none\"/>\n",
+            path
+        ));
+    }
+    svg.push_str("</g>\n");
+    if options.legend {
+        for (state, c) in colours.iter().enumerate() {
+            let y = top + state * LEGEND_ROW;
+            svg.push_str(&rect(board_width + 8, y + 2, 12, 12, c));
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"12\">{}</text>\n",
+                board_width + 26,
+                y + 12,
+                state
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn title(board: &Board) -> String {
+    match &board.automata {
+        Some(automata) => {
+            let rules: Vec<String> = automata.rule_string.iter().map(|c| c.to_string()).collect();
+            format!("rules {} fitness {}", rules.join(""), board.fitness)
+        }
+        None => "None".to_string(),
+    }
+}
+
+fn rect(x: usize, y: usize, width: usize, height: usize, c: &Colour) -> String {
+    // the hex colour is always opaque, so transparency
+    // is given separately
+    let opacity = if c.a < 1.0 { format!(" fill-opacity=\"{}\"", c.a) } else { String::new() };
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#{:02x}{:02x}{:02x}\"{}/>\n",
+        x, y, width, height, c.r, c.g, c.b, opacity
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::Automata;
+
+    fn board() -> Board {
+        let mut a1d = Automata::new1d(3, 1);
+        a1d.rule_string = vec![0, 1, 2, 2, 0, 0, 1];
+        let mut board = Board::initialise(5, 2);
+        board.fill(a1d, &[1, 1, 2]);
+        board
+    }
+
+    fn colours() -> Vec<Colour> {
+        vec![
+            Colour::new(255, 255, 255, 1.0),
+            Colour::new(255, 0, 0, 0.5),
+            Colour::new(0, 0, 255, 1.0),
+        ]
+    }
+
+    #[test]
+    fn merged_runs() {
+        // the first row is 0 1 1 2 0: a background rect,
+        // one rect for both 1s and one for the 2
+        let svg = board_to_svg(&board(), &colours(), &SvgOptions::default());
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert!(svg.contains("<rect x=\"10\" y=\"0\" width=\"20\" height=\"10\" fill=\"#ff0000\" fill-opacity=\"0.5\"/>"));
+        assert!(svg.contains("<rect x=\"30\" y=\"0\" width=\"10\" height=\"10\" fill=\"#0000ff\"/>"));
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"50\" height=\"20\""));
+        assert!(!svg.contains("<path"));
+        assert!(!svg.contains("<text"));
+    }
+
+    #[test]
+    fn grid_legend_and_title() {
+        let options = SvgOptions {
+            scale: 4,
+            grid: true,
+            legend: true,
+            title: true,
+        };
+        let svg = board_to_svg(&board(), &colours(), &options);
+        assert!(svg.contains("<path d=\"M4 0V8M8 0V8M12 0V8M16 0V8M0 4H20\""));
+        assert!(svg.contains(">rules 0122001 fitness 3</text>"));
+        // one swatch and label per state
+        assert_eq!(svg.matches("<text").count(), 1 + 3);
+        assert!(svg.contains("width=\"80\" height=\"72\""));
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use crate::colour::Colour as Colour;
+
+// upper half block: the foreground colour fills the top half
+// of the character and the background colour the bottom half
+const HALF_BLOCK: char = '\u{2580}';
+const RESET: &str = "\x1b[0m";
+
+pub fn render(cell_states: &[Vec<usize>], colours: &[Colour]) -> String {
+    // two rows of cells per line of text, drawn with 24-bit
+    // ANSI colours; colours are only sent when they change
+    let mut output = String::new();
+    for rows in cell_states.chunks(2) {
+        let mut last: Option<(usize, Option<usize>)> = None;
+        for (i, top) in rows[0].iter().enumerate() {
+            let bottom = rows.get(1).map(|row| row[i]);
+            if last != Some((*top, bottom)) {
+                output.push_str(&foreground(colour(colours, *top)));
+                match bottom {
+                    Some(state) => output.push_str(&background(colour(colours, state))),
+                    // odd number of rows: leave the terminal's own
+                    // background under the last row
+                    None => output.push_str("\x1b[49m"),
+                }
+                last = Some((*top, bottom));
+            }
+            output.push(HALF_BLOCK);
+        }
+        output.push_str(RESET);
+        output.push('\n');
+    }
+    output
+}
+
+pub fn replay<W: Write>(
+    writer: &mut W,
+    frames: &[Vec<Vec<usize>>],
+    colours: &[Colour],
+    delay: Duration,
+) -> std::io::Result<()> {
+    // clear the screen once, then draw each frame over
+    // the last one from the top left corner
+    write!(writer, "\x1b[2J")?;
+    for (i, frame) in frames.iter().enumerate() {
+        write!(writer, "\x1b[H{}", render(frame, colours))?;
+        writer.flush()?;
+        if i + 1 < frames.len() {
+            thread::sleep(delay);
+        }
+    }
+    Ok(())
+}
+
+fn colour(colours: &[Colour], state: usize) -> &Colour {
+    colours.get(state).expect("Not enough colours for all cell states")
+}
+
+fn foreground(c: &Colour) -> String {
+    format!("\x1b[38;2;{};{};{}m", c.r, c.g, c.b)
+}
+
+fn background(c: &Colour) -> String {
+    format!("\x1b[48;2;{};{};{}m", c.r, c.g, c.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colours() -> Vec<Colour> {
+        vec![Colour::new(255, 255, 255, 1.0), Colour::new(255, 0, 0, 1.0)]
+    }
+
+    #[test]
+    fn half_blocks() {
+        let output = render(&[vec![0, 0, 1], vec![0, 0, 1], vec![1, 0, 0]], &colours());
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].matches(HALF_BLOCK).count(), 3);
+        // the first two columns share colours, so only two changes
+        assert_eq!(
+            lines[0],
+            "\x1b[38;2;255;255;255m\x1b[48;2;255;255;255m\u{2580}\u{2580}\x1b[38;2;255;0;0m\x1b[48;2;255;0;0m\u{2580}\x1b[0m"
+        );
+        assert!(lines[1].starts_with("\x1b[38;2;255;0;0m\x1b[49m\u{2580}"));
+    }
+
+    #[test]
+    fn replay_frames() {
+        let frames = vec![vec![vec![0, 1]], vec![vec![1, 0]]];
+        let mut output = Vec::new();
+        replay(&mut output, &frames, &colours(), Duration::from_millis(0)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("\x1b[2J\x1b[H"));
+        assert_eq!(output.matches("\x1b[H").count(), 2);
+    }
+}
@@ -3,9 +3,57 @@ extern crate rand;
 use rand::seq::SliceRandom;
 use rand::Rng;
 
+use crate::automata::Automata as Automata;
 use crate::board::Board as Board;
-use crate::mutation::{crossover, mutate};
+use crate::cache::FitnessCache;
+use crate::automata::{next_id, QUIESCENT_RULES};
+use crate::adaptation::{MutationControl, MutationSchedule};
+use crate::diversity::{is_duplicate, DuplicatePolicy};
+use crate::mutation::{Crossover, CrossoverOperator, KPoint};
+use crate::novelty;
+use crate::novelty::{Archive, Objective};
+use crate::selection::Selection;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Replacement {
+    // children replace the two worst boards in the sample
+    Worst,
+    // children replace the two worst boards in the sample,
+    // but only if the child has a higher fitness
+    IfBetter,
+    // children replace their own parents
+    Parents,
+    // children replace two random boards in the sample
+    Random,
+    // each child replaces the board in the sample with the
+    // most similar rule string (deterministic crowding)
+    Crowding,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatingEvent {
+    // positions in the population that children went into
+    pub replaced: Vec<usize>,
+    // children thrown away by the replacement strategy
+    pub rejected: usize,
+    // children thrown away for already being in the population
+    pub duplicates: usize,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MatingConfig<'a> {
+    // the settings a mating event reads, borrowed from the
+    // experiment config or made up by tournament
+    pub selection: Selection,
+    pub crossover: CrossoverOperator,
+    pub duplicates: DuplicatePolicy,
+    pub locked_positions: &'a [usize],
+    pub start_population: &'a [usize],
+    pub num_iters: usize,
+    pub replacement: Replacement,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn tournament(
     tournament_size: usize,
     population: &mut Vec<Board>,
@@ -13,76 +61,248 @@ pub fn tournament(
     max_mutations: usize,
     start_population: &[usize],
     num_iters: usize,
-) {
+    replacement: Replacement,
+    cache: &mut FitnessCache,
+) -> MatingEvent {
+    // breed the best two boards of a random sample, with
+    // the children put back by the replacement strategy
     assert!(tournament_size <= population.len());
+    let config = tournament_config(tournament_size, max_cuts, start_population, num_iters, replacement);
+    let mut mutation = MutationControl::new(MutationSchedule::Fixed, max_mutations);
+
+    let sample = sample_population(tournament_size, population.len());
+    let fitness_values: Vec<f64> = sample.iter().map(|i| population[*i].fitness as f64).collect();
+    mating_event(population, &sample, &fitness_values, &config, &mut mutation, cache)
+}
 
+#[allow(clippy::too_many_arguments)]
+pub fn novelty_tournament(
+    tournament_size: usize,
+    population: &mut [Board],
+    max_cuts: usize,
+    max_mutations: usize,
+    start_population: &[usize],
+    num_iters: usize,
+    replacement: Replacement,
+    archive: &mut Archive,
+    objective: Objective,
+    cache: &mut FitnessCache,
+) -> MatingEvent {
+    // same as tournament, except the sample is ranked by the
+    // objective (novelty, fitness or a blend of the two)
+    // instead of by fitness alone
+    assert!(tournament_size <= population.len());
+    let config = tournament_config(tournament_size, max_cuts, start_population, num_iters, replacement);
+    let mut mutation = MutationControl::new(MutationSchedule::Fixed, max_mutations);
+
+    let sample = sample_population(tournament_size, population.len());
+    let scores = novelty::scores(population, &sample, archive, objective);
+    let event = mating_event(population, &sample, &scores, &config, &mut mutation, cache);
+
+    // the children are added to the archive if their
+    // behaviour is novel enough
+    if objective != Objective::Fitness {
+        novelty::update_archive(archive, population, &event.replaced);
+    }
+    event
+}
+
+fn tournament_config(
+    tournament_size: usize,
+    max_cuts: usize,
+    start_population: &[usize],
+    num_iters: usize,
+    replacement: Replacement,
+) -> MatingConfig<'_> {
+    // a tournament as big as the sample picks the best two;
+    // the quiescent rule is locked and duplicates are allowed,
+    // as they always were
+    MatingConfig {
+        selection: Selection::Tournament(tournament_size),
+        crossover: CrossoverOperator::KPoint(KPoint { max_cuts }),
+        duplicates: DuplicatePolicy::Allow,
+        locked_positions: &QUIESCENT_RULES,
+        start_population,
+        num_iters,
+        replacement,
+    }
+}
+
+pub(crate) fn sample_population(tournament_size: usize, population_size: usize) -> Vec<usize> {
     let mut rng = rand::thread_rng();
 
-    let mut sample: Vec<usize> = (0..population.len()).collect();
+    let mut sample: Vec<usize> = (0..population_size).collect();
     sample.shuffle(&mut rng);
 
     // limit size of sample so it matches population size
-    let sample = &sample[..tournament_size];
+    sample.truncate(tournament_size);
+    sample
+}
 
-    let mut fitness_values: Vec<usize> = Vec::with_capacity(tournament_size);
+pub(crate) fn mating_event(
+    population: &mut [Board],
+    sample: &[usize],
+    scores: &[f64],
+    config: &MatingConfig,
+    mutation: &mut MutationControl,
+    cache: &mut FitnessCache,
+) -> MatingEvent {
+    // pick parents from the sample, breed them and put their
+    // children back according to the replacement strategy;
+    // scores are for the boards in the sample
+    let mut rng = rand::thread_rng();
+    let mut event = MatingEvent::default();
+    let (parent1, parent2) = config.selection.select_pair(scores, &mut rng);
+    let (child1, child2) = breed(
+        population,
+        sample[parent1],
+        sample[parent2],
+        &config.crossover,
+        mutation,
+        config.locked_positions,
+    );
 
-    for i in 0..tournament_size {
-        fitness_values.push(population[sample[i]].fitness);
+    // duplicates are dealt with before running the children,
+    // since running them would not tell us anything new
+    let mut parents = Vec::with_capacity(2);
+    let mut children: Vec<Board> = Vec::with_capacity(2);
+    for (mut child, parent) in vec![child1, child2].into_iter().zip(vec![parent1, parent2]) {
+        let check = config.duplicates != DuplicatePolicy::Allow;
+        let mut duplicate = check && (is_duplicate(&child, population) || is_duplicate(&child, &children));
+        if let DuplicatePolicy::Remutate { attempts } = config.duplicates {
+            for _ in 0..attempts {
+                if !duplicate {
+                    break;
+                }
+                mutation.mutate(&mut child, config.locked_positions);
+                duplicate = is_duplicate(&child, population) || is_duplicate(&child, &children);
+            }
+        }
+        if duplicate {
+            event.duplicates += 1;
+            continue;
+        }
+        let parent_board = &population[sample[parent]];
+        let (width, height) = (parent_board.width(), parent_board.height());
+        let board = cache.evaluate(child, width, height, config.start_population, config.num_iters);
+        mutation.record(parent_board.fitness < board.fitness);
+        children.push(board);
+        parents.push(parent);
     }
-    let (max_idx1, max_idx2) = max_two_indices(&fitness_values);
-    let mut child1 = population[sample[max_idx1]]
+
+    let targets = replacement_targets(config.replacement, population, sample, scores, &parents, &children, &mut rng);
+    for (child, target) in children.into_iter().zip(targets) {
+        match target {
+            Some(i) => {
+                population[i] = child;
+                event.replaced.push(i);
+            }
+            None => event.rejected += 1,
+        }
+    }
+    event
+}
+
+pub(crate) fn breed<C: Crossover>(
+    population: &[Board],
+    parent1: usize,
+    parent2: usize,
+    operator: &C,
+    mutation: &MutationControl,
+    locked: &[usize],
+) -> (Automata, Automata) {
+    let mut child1 = population[parent1]
         .automata
         .as_ref()
         .expect("Board is missing automata.")
         .clone();
-    let mut child2 = population[sample[max_idx2]]
+    let mut child2 = population[parent2]
         .automata
         .as_ref()
         .expect("Board is missing automata.")
         .clone();
 
+    // the children are new automata, bred from both parents
+    let parents = vec![child1.id, child2.id];
+    child1.id = next_id();
+    child1.parents = parents.clone();
+    child2.id = next_id();
+    child2.parents = parents;
+    for child in [&mut child1, &mut child2].iter_mut() {
+        child.cut_points.clear();
+        child.mutated_positions.clear();
+    }
+
     // crossover and then mutate
-    crossover(&mut child1, &mut child2, max_cuts);
-    mutate(&mut child1, max_mutations);
-    mutate(&mut child2, max_mutations);
-
-    // replace worst 2 with mutated parents
-    // in the case where the population size is less than 4,
-    // then the children should replace the parents
-    let (min_idx1, min_idx2) = min_two_indices(&fitness_values);
-    population[sample[min_idx1]].empty();
-    population[sample[min_idx1]].run(child1, start_population, num_iters);
-    population[sample[min_idx2]].empty();
-    population[sample[min_idx2]].run(child2, start_population, num_iters);
+    operator.crossover_with_locks(&mut child1, &mut child2, locked);
+    mutation.mutate(&mut child1, locked);
+    mutation.mutate(&mut child2, locked);
+    (child1, child2)
 }
 
-fn max_two_indices(s: &Vec<usize>) -> (usize, usize) {
-    // given a borrowed vector, pick the indices where
-    // the largest two values reside and return those
-    // indices as a tuple (max1, max2), where
-    // s[max2] < s[max1]
-    assert!(1 < s.len());
-    let mut max1: usize;
-    let mut max2: usize;
-    if s[0] < s[1] {
-        max1 = 1;
-        max2 = 0;
-    } else {
-        max1 = 0;
-        max2 = 1;
-    }
-    for i in 2..s.len() {
-        if s[max1] < s[i] {
-            max2 = max1;
-            max1 = i;
-        } else if s[max2] < s[i] {
-            max2 = i;
+fn replacement_targets<R: Rng>(
+    replacement: Replacement,
+    population: &[Board],
+    sample: &[usize],
+    scores: &[f64],
+    parents: &[usize],
+    children: &[Board],
+    rng: &mut R,
+) -> Vec<Option<usize>> {
+    // decide which board in the population each child replaces,
+    // None means the child is thrown away; the parent of each
+    // child is given as a position in the sample
+    assert!(children.len() <= 2);
+    assert_eq!(parents.len(), children.len());
+    assert!(1 < sample.len());
+    match replacement {
+        Replacement::Worst => {
+            let (min_idx1, min_idx2) = min_two_indices(scores);
+            vec![Some(sample[min_idx1]), Some(sample[min_idx2])]
+        }
+        Replacement::IfBetter => {
+            let (min_idx1, min_idx2) = min_two_indices(scores);
+            [sample[min_idx1], sample[min_idx2]]
+                .iter()
+                .zip(children)
+                .map(|(i, child)| {
+                    if population[*i].fitness < child.fitness {
+                        Some(*i)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        }
+        Replacement::Parents => parents.iter().map(|i| Some(sample[*i])).collect(),
+        Replacement::Random => sample
+            .choose_multiple(rng, children.len())
+            .map(|i| Some(*i))
+            .collect(),
+        Replacement::Crowding => {
+            let mut remaining = sample.to_vec();
+            let mut targets = Vec::with_capacity(children.len());
+            for child in children {
+                let child_automata = child.automata.as_ref().expect("Board is missing automata.");
+                let mut closest = 0;
+                let mut closest_distance = usize::MAX;
+                for (j, i) in remaining.iter().enumerate() {
+                    let other = population[*i].automata.as_ref().expect("Board is missing automata.");
+                    let distance = child_automata.distance(other);
+                    if distance < closest_distance {
+                        closest = j;
+                        closest_distance = distance;
+                    }
+                }
+                // the other child cannot replace the same board
+                targets.push(Some(remaining.remove(closest)));
+            }
+            targets
         }
     }
-    (max1, max2)
 }
 
-fn min_two_indices(s: &Vec<usize>) -> (usize, usize) {
+pub(crate) fn min_two_indices<T: PartialOrd>(s: &[T]) -> (usize, usize) {
     // given a borrowed vector, pick the indices where
     // the smallest two values reside and return those
     // indices as a tuple (min1, min2), where
@@ -116,37 +336,6 @@ fn min_two_indices(s: &Vec<usize>) -> (usize, usize) {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_maximum() {
-        let mut result: (usize, usize);
-        let v0 = vec![0, 4, 2, 5, 3];
-        result = max_two_indices(&v0);
-        assert_eq!(result, (3, 1));
-
-        let v1 = vec![0, 0, 0, 0, 0];
-        result = max_two_indices(&v1);
-        assert_eq!(result, (0, 1));
-
-        let v2 = vec![0, 0, 0, 0, 1];
-        result = max_two_indices(&v2);
-        assert_eq!(result, (4, 0));
-
-        let v3 = vec![0, 0, 435, 22, 7];
-        result = max_two_indices(&v3);
-        assert_eq!(result, (2, 3));
-
-        let v4 = vec![5454, 33, 435, 22, 7];
-        result = max_two_indices(&v4);
-        assert_eq!(result, (0, 2));
-    }
-
-    #[test]
-    #[should_panic(expected = "assertion failed")]
-    fn maximum_2_of_1() {
-        let v0 = vec![0];
-        let result = max_two_indices(&v0);
-        assert!(false, result);
-    }
     #[test]
     fn test_minimum() {
         let mut result: (usize, usize);
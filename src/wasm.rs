@@ -0,0 +1,257 @@
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::board::Board as Board;
+use crate::experiment::{Config, Experiment};
+
+#[wasm_bindgen]
+pub struct WasmExperiment {
+    experiment: Experiment,
+}
+
+#[wasm_bindgen]
+pub struct WasmBoard {
+    board: Board,
+}
+
+#[wasm_bindgen]
+impl WasmExperiment {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: &JsValue) -> Result<WasmExperiment, JsValue> {
+        // the config object uses the names of the web form in
+        // camel case, e.g. { numMevs: 100, is2d: true }; anything
+        // left out keeps its default value
+        let config = config_from_js(config)?;
+        WasmExperiment::from_config(config).map_err(|message| JsValue::from_str(&message))
+    }
+
+    pub fn step(&mut self, n: usize) -> usize {
+        // run up to n more steps, stopping early if the
+        // experiment has finished; returns the steps run so far
+        for _ in 0..n {
+            if self.experiment.finished() {
+                break;
+            }
+            self.experiment.step();
+        }
+        self.experiment.mev
+    }
+
+    pub fn finished(&mut self) -> bool {
+        self.experiment.finished()
+    }
+
+    pub fn best(&self) -> WasmBoard {
+        WasmBoard {
+            board: self.experiment.best().clone(),
+        }
+    }
+
+    pub fn board(&self, index: usize) -> Option<WasmBoard> {
+        self.experiment.boards.get(index).map(|board| WasmBoard { board: board.clone() })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn mev(&self) -> usize {
+        self.experiment.mev
+    }
+
+    #[wasm_bindgen(js_name = populationStats)]
+    pub fn population_stats(&self) -> Result<JsValue, JsValue> {
+        let e = &self.experiment;
+        let stats = Object::new();
+        let values = [
+            ("mev", e.mev as f64),
+            ("evaluations", e.evaluations as f64),
+            ("bestFitness", e.best().fitness as f64),
+            ("meanFitness", e.mean_fitness()),
+            ("worstFitness", e.boards.iter().map(|b| b.fitness).min().unwrap_or(0) as f64),
+            ("numApoptotic", e.boards.iter().filter(|b| b.apoptotic).count() as f64),
+            ("meanDistance", e.diversity.mean_distance),
+            ("uniqueRules", e.diversity.unique_rules as f64),
+            ("meanEntropy", e.diversity.mean_entropy()),
+        ];
+        for (key, value) in values.iter() {
+            Reflect::set(&stats, &JsValue::from_str(key), &JsValue::from_f64(*value))?;
+        }
+        let reason = e.stop_reason.map_or(JsValue::NULL, |r| JsValue::from_str(&r.to_string()));
+        Reflect::set(&stats, &JsValue::from_str("stopReason"), &reason)?;
+        Ok(stats.into())
+    }
+}
+
+impl WasmExperiment {
+    pub fn from_config(config: Config) -> Result<WasmExperiment, String> {
+        // std::time::Instant panics in the browser
+        if config.time_budget.is_some() {
+            return Err("a time budget needs a clock, which is not available in wasm".to_string());
+        }
+        config.validate()?;
+        Ok(WasmExperiment {
+            experiment: Experiment::new(config),
+        })
+    }
+
+    pub fn experiment(&self) -> &Experiment {
+        &self.experiment
+    }
+}
+
+#[wasm_bindgen]
+impl WasmBoard {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.board.width()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        // number of rows in the cell data (the number of
+        // generations for a 1D board)
+        self.board.cell_states.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn fitness(&self) -> usize {
+        self.board.fitness
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn apoptotic(&self) -> bool {
+        self.board.apoptotic
+    }
+
+    pub fn cells(&self) -> Vec<u32> {
+        // cell states row by row, as a Uint32Array
+        self.board.cell_states.iter().flatten().map(|c| *c as u32).collect()
+    }
+
+    #[wasm_bindgen(js_name = liveCounts)]
+    pub fn live_counts(&self) -> Vec<u32> {
+        self.board.live_counts.iter().map(|c| *c as u32).collect()
+    }
+
+    #[wasm_bindgen(js_name = ruleString)]
+    pub fn rule_string(&self) -> Vec<u32> {
+        match &self.board.automata {
+            Some(automata) => automata.rule_string.iter().map(|c| *c as u32).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl WasmBoard {
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+}
+
+fn config_from_js(value: &JsValue) -> Result<Config, JsValue> {
+    if !value.is_object() {
+        return Err(JsValue::from_str("the config must be an object"));
+    }
+    let mut config = Config::default();
+    let get = |key: &str| Reflect::get(value, &JsValue::from_str(key));
+    if !get("timeBudget")?.is_undefined() {
+        return Err(JsValue::from_str("timeBudget needs a clock, which is not available in wasm"));
+    }
+    let number = |key: &str| -> Result<Option<usize>, JsValue> {
+        let v = get(key)?;
+        if v.is_undefined() || v.is_null() {
+            return Ok(None);
+        }
+        match v.as_f64() {
+            Some(n) if 0.0 <= n && n.fract() == 0.0 => Ok(Some(n as usize)),
+            _ => Err(JsValue::from_str(&format!("{} must be a whole number", key))),
+        }
+    };
+    if let Some(n) = number("numMevs")? {
+        config.num_mevs = n;
+    }
+    if let Some(n) = number("populationSize")? {
+        config.population_size = n;
+    }
+    if let Some(n) = number("numStates")? {
+        config.num_states = n;
+    }
+    if let Some(n) = number("tournamentSize")? {
+        config.tournament_size = n;
+    }
+    if let Some(n) = number("neighbourhoodSize")? {
+        if n > u8::MAX as usize {
+            return Err(JsValue::from_str(&format!("neighbourhoodSize must be at most {}", u8::MAX)));
+        }
+        config.neighbourhood_size = n as u8;
+    }
+    if let Some(n) = number("width")? {
+        config.width = n;
+    }
+    if let Some(n) = number("height")? {
+        config.height = n;
+    }
+    let num_iters = number("numIters")?;
+    let max_cuts = number("maxCuts")?;
+    let max_mutations = number("maxMutations")?;
+    if let Some(b) = get("is2d")?.as_bool() {
+        config.is_2d = b;
+    }
+    let start = get("startPopulation")?;
+    if Array::is_array(&start) {
+        config.start_population = Array::from(&start)
+            .iter()
+            .map(|v| match v.as_f64() {
+                Some(n) if 0.0 <= n && n.fract() == 0.0 => Ok(n as usize),
+                _ => Err(JsValue::from_str("startPopulation must hold whole numbers")),
+            })
+            .collect::<Result<Vec<usize>, JsValue>>()?;
+    }
+    config.fit_defaults(num_iters, max_cuts, max_mutations);
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            population_size: 5,
+            num_states: 3,
+            tournament_size: 4,
+            width: 15,
+            height: 15,
+            num_iters: 8,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn steps_and_cells() {
+        let mut experiment = WasmExperiment::from_config(config()).unwrap();
+        assert_eq!(experiment.step(3), 3);
+        assert_eq!(experiment.mev(), 3);
+        // never runs past the configured number of steps
+        assert_eq!(experiment.step(100), 10);
+        assert!(experiment.finished());
+        let best = experiment.best();
+        assert_eq!(best.cells().len(), best.width() * best.height());
+        assert_eq!(best.fitness(), experiment.experiment().best().fitness);
+        assert_eq!(best.rule_string().len(), 19);
+        assert!(experiment.board(5).is_none());
+    }
+
+    #[test]
+    fn invalid_config() {
+        let bad = Config {
+            start_population: vec![5],
+            ..config()
+        };
+        assert!(WasmExperiment::from_config(bad).is_err());
+        let timed = Config {
+            time_budget: Some(std::time::Duration::from_secs(1)),
+            ..config()
+        };
+        assert!(timed.validate().is_ok());
+        assert!(WasmExperiment::from_config(timed).is_err());
+    }
+}
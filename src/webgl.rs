@@ -0,0 +1,424 @@
+use js_sys::Float32Array;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, WebGlBuffer, WebGlProgram, WebGlShader, WebGlTexture, WebGlUniformLocation};
+use web_sys::WebGlRenderingContext as GL;
+
+use crate::colour::Colour as Colour;
+use crate::image::Image as Image;
+use crate::palette::Palette as Palette;
+use crate::wasm::{WasmBoard, WasmExperiment};
+
+type Matrix = [f32; 16];
+
+const VERTEX_SHADER: &str = "
+attribute vec2 position;
+attribute vec2 texcoord;
+uniform mat4 transform;
+varying vec2 v_texcoord;
+void main() {
+    gl_Position = transform * vec4(position, 0.0, 1.0);
+    v_texcoord = texcoord;
+}";
+
+const FRAGMENT_SHADER: &str = "
+precision mediump float;
+uniform sampler2D cells;
+varying vec2 v_texcoord;
+void main() {
+    vec4 colour = texture2D(cells, v_texcoord);
+    // dead cells are see-through in the volume
+    if (colour.a == 0.0) {
+        discard;
+    }
+    gl_FragColor = colour;
+}";
+
+// a square covering clip space as a triangle strip of
+// x, y, s, t; the top row of a texture is at t = 0
+const QUAD: [f32; 16] = [
+    -1.0, 1.0, 0.0, 0.0, //
+    1.0, 1.0, 1.0, 0.0, //
+    -1.0, -1.0, 0.0, 1.0, //
+    1.0, -1.0, 1.0, 1.0,
+];
+
+// pitch is kept short of straight up or down
+const MAX_PITCH: f32 = 1.5;
+
+pub fn layer_pixels(cell_states: &[Vec<usize>], colours: &[Colour], transparent: bool) -> Vec<u8> {
+    // one RGBA texel per cell; state 0 can be made fully
+    // transparent so that only live cells show in a stack
+    let mut colours = colours.to_vec();
+    if transparent {
+        if let Some(background) = colours.first_mut() {
+            background.a = 0.0;
+        }
+    }
+    Image::from_cells(cell_states, &colours, 1).pixels
+}
+
+#[wasm_bindgen]
+pub struct GlRenderer {
+    canvas: HtmlCanvasElement,
+    gl: GL,
+    program: WebGlProgram,
+    quad: WebGlBuffer,
+    transform: WebGlUniformLocation,
+    palette: Palette,
+    // a single texture for a flat board, one per layer
+    // for a volume
+    textures: Vec<WebGlTexture>,
+    columns: usize,
+    rows: usize,
+    // angles of the volume in radians
+    yaw: f32,
+    pitch: f32,
+}
+
+#[wasm_bindgen]
+impl GlRenderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement) -> Result<GlRenderer, JsValue> {
+        let gl = canvas
+            .get_context("webgl")?
+            .ok_or_else(|| JsValue::from_str("the canvas has no webgl context"))?
+            .dyn_into::<GL>()?;
+        let vertex = compile_shader(&gl, GL::VERTEX_SHADER, VERTEX_SHADER)?;
+        let fragment = compile_shader(&gl, GL::FRAGMENT_SHADER, FRAGMENT_SHADER)?;
+        let program = link_program(&gl, &vertex, &fragment)?;
+        gl.use_program(Some(&program));
+
+        let quad = gl.create_buffer().ok_or_else(|| JsValue::from_str("unable to create buffer"))?;
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad));
+        gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &Float32Array::from(&QUAD[..]), GL::STATIC_DRAW);
+        let stride = 4 * 4;
+        let position = gl.get_attrib_location(&program, "position") as u32;
+        gl.vertex_attrib_pointer_with_i32(position, 2, GL::FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(position);
+        let texcoord = gl.get_attrib_location(&program, "texcoord") as u32;
+        gl.vertex_attrib_pointer_with_i32(texcoord, 2, GL::FLOAT, false, stride, 2 * 4);
+        gl.enable_vertex_attrib_array(texcoord);
+
+        let transform = gl
+            .get_uniform_location(&program, "transform")
+            .ok_or_else(|| JsValue::from_str("the shader has no transform"))?;
+        let cells = gl.get_uniform_location(&program, "cells");
+        gl.uniform1i(cells.as_ref(), 0);
+        gl.active_texture(GL::TEXTURE0);
+        // rows of cells are not a multiple of four bytes
+        // wide in general
+        gl.pixel_storei(GL::UNPACK_ALIGNMENT, 1);
+
+        Ok(GlRenderer {
+            canvas,
+            gl,
+            program,
+            quad,
+            transform,
+            palette: Palette::Viridis,
+            textures: Vec::new(),
+            columns: 0,
+            rows: 0,
+            yaw: 0.6,
+            pitch: 0.4,
+        })
+    }
+
+    #[wasm_bindgen(js_name = setPalette)]
+    pub fn set_palette(&mut self, name: &str) -> Result<(), JsValue> {
+        self.palette = name.parse().map_err(|message: String| JsValue::from_str(&message))?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = drawBoard)]
+    pub fn draw_board(&mut self, board: &WasmBoard) -> Result<(), JsValue> {
+        // the board as a single flat texture, as large as
+        // it fits in the canvas
+        let board = board.board();
+        let colours = board.colours(&self.palette);
+        self.load(std::slice::from_ref(&board.cell_states), &colours, false)?;
+        let (sx, sy) = fit(self.columns, self.rows, self.canvas.width(), self.canvas.height());
+        self.begin(false);
+        self.gl.bind_texture(GL::TEXTURE_2D, Some(&self.textures[0]));
+        self.gl.uniform_matrix4fv_with_f32_array(Some(&self.transform), false, &scaling(sx, sy, 1.0));
+        self.gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = loadVolume)]
+    pub fn load_volume(&mut self, experiment: &WasmExperiment, board: &WasmBoard) -> Result<(), JsValue> {
+        // upload every layer of a 2D run, to be drawn
+        // stacked in time order by draw_volume
+        let config = &experiment.experiment().config;
+        let board = board.board();
+        if !config.is_2d {
+            return Err(JsValue::from_str("only 2D boards have layers to stack"));
+        }
+        let layers = board.history(&config.start_population, config.num_iters);
+        let colours = board.colours(&self.palette);
+        self.load(&layers, &colours, true)
+    }
+
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        // turn the volume by the given angles in radians,
+        // e.g. from the movement of a mouse drag
+        self.yaw = (self.yaw + yaw) % std::f32::consts::TAU;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    #[wasm_bindgen(js_name = drawVolume)]
+    pub fn draw_volume(&self) {
+        // each layer is a square one cell deep along the
+        // time axis, with the first layer at the back
+        let aspect = self.canvas.width() as f32 / self.canvas.height().max(1) as f32;
+        let view = view(self.columns, self.rows, self.textures.len(), aspect, self.yaw, self.pitch);
+        self.begin(true);
+        for (i, texture) in self.textures.iter().enumerate() {
+            let transform = multiply(&view, &translation(0.0, 0.0, layer_depth(i, self.textures.len(), self.columns, self.rows)));
+            self.gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+            self.gl.uniform_matrix4fv_with_f32_array(Some(&self.transform), false, &transform);
+            self.gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
+        }
+    }
+}
+
+impl GlRenderer {
+    fn load(&mut self, layers: &[Vec<Vec<usize>>], colours: &[Colour], transparent: bool) -> Result<(), JsValue> {
+        // replace the textures with one for each layer
+        for texture in self.textures.drain(..) {
+            self.gl.delete_texture(Some(&texture));
+        }
+        self.rows = layers.first().map_or(0, |layer| layer.len());
+        self.columns = layers.first().and_then(|layer| layer.first()).map_or(0, |row| row.len());
+        for layer in layers {
+            let texture = self.gl.create_texture().ok_or_else(|| JsValue::from_str("unable to create texture"))?;
+            self.gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+            // cells stay sharp, and clamping lets the board
+            // be any size without mipmaps
+            self.gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+            self.gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+            self.gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+            self.gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+            self.gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                GL::TEXTURE_2D,
+                0,
+                GL::RGBA as i32,
+                self.columns as i32,
+                self.rows as i32,
+                0,
+                GL::RGBA,
+                GL::UNSIGNED_BYTE,
+                Some(&layer_pixels(layer, colours, transparent)),
+            )?;
+            self.textures.push(texture);
+        }
+        Ok(())
+    }
+
+    fn begin(&self, depth: bool) {
+        let gl = &self.gl;
+        gl.viewport(0, 0, self.canvas.width() as i32, self.canvas.height() as i32);
+        gl.use_program(Some(&self.program));
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.quad));
+        if depth {
+            gl.enable(GL::DEPTH_TEST);
+        } else {
+            gl.disable(GL::DEPTH_TEST);
+        }
+        gl.clear_color(1.0, 1.0, 1.0, 1.0);
+        gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+    }
+}
+
+fn compile_shader(gl: &GL, shader_type: u32, source: &str) -> Result<WebGlShader, JsValue> {
+    let shader = gl.create_shader(shader_type).ok_or_else(|| JsValue::from_str("unable to create shader"))?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+    if gl.get_shader_parameter(&shader, GL::COMPILE_STATUS).as_bool().unwrap_or(false) {
+        Ok(shader)
+    } else {
+        let log = gl.get_shader_info_log(&shader).unwrap_or_default();
+        Err(JsValue::from_str(&format!("unable to compile shader: {}", log)))
+    }
+}
+
+fn link_program(gl: &GL, vertex: &WebGlShader, fragment: &WebGlShader) -> Result<WebGlProgram, JsValue> {
+    let program = gl.create_program().ok_or_else(|| JsValue::from_str("unable to create program"))?;
+    gl.attach_shader(&program, vertex);
+    gl.attach_shader(&program, fragment);
+    gl.link_program(&program);
+    if gl.get_program_parameter(&program, GL::LINK_STATUS).as_bool().unwrap_or(false) {
+        Ok(program)
+    } else {
+        let log = gl.get_program_info_log(&program).unwrap_or_default();
+        Err(JsValue::from_str(&format!("unable to link program: {}", log)))
+    }
+}
+
+fn fit(columns: usize, rows: usize, width: u32, height: u32) -> (f32, f32) {
+    // scale the clip space square so the board keeps its
+    // shape and fills as much of the canvas as it can
+    if columns == 0 || rows == 0 || width == 0 || height == 0 {
+        return (1.0, 1.0);
+    }
+    let board = columns as f32 / rows as f32;
+    let canvas = width as f32 / height as f32;
+    if board > canvas {
+        (1.0, canvas / board)
+    } else {
+        (board / canvas, 1.0)
+    }
+}
+
+fn cell_size(columns: usize, rows: usize) -> f32 {
+    // the longest side of the board is two units long
+    2.0 / columns.max(rows).max(1) as f32
+}
+
+fn layer_depth(layer: usize, num_layers: usize, columns: usize, rows: usize) -> f32 {
+    // layers are one cell apart and centred on the origin
+    (layer as f32 - (num_layers as f32 - 1.0) / 2.0) * cell_size(columns, rows)
+}
+
+fn view(columns: usize, rows: usize, num_layers: usize, aspect: f32, yaw: f32, pitch: f32) -> Matrix {
+    // perspective from far enough back that the whole
+    // volume stays in view however it is turned
+    let size = cell_size(columns, rows);
+    let (x, y, z) = (columns as f32 * size / 2.0, rows as f32 * size / 2.0, num_layers as f32 * size / 2.0);
+    let radius = (x * x + y * y + z * z).sqrt().max(1.0);
+    let fov = std::f32::consts::FRAC_PI_4;
+    let distance = radius / (fov / 2.0).sin();
+    let projection = perspective(fov, aspect, distance - radius, distance + radius);
+    let model = multiply(&rotation_x(pitch), &multiply(&rotation_y(yaw), &scaling(x, y, 1.0)));
+    multiply(&projection, &multiply(&translation(0.0, 0.0, -distance), &model))
+}
+
+// 4x4 matrices are stored column by column, as WebGL expects
+
+fn identity() -> Matrix {
+    scaling(1.0, 1.0, 1.0)
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [0.0; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            m[column * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[column * 4 + k]).sum();
+        }
+    }
+    m
+}
+
+fn scaling(x: f32, y: f32, z: f32) -> Matrix {
+    [
+        x, 0.0, 0.0, 0.0, //
+        0.0, y, 0.0, 0.0, //
+        0.0, 0.0, z, 0.0, //
+        0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+fn translation(x: f32, y: f32, z: f32) -> Matrix {
+    let mut m = identity();
+    m[12] = x;
+    m[13] = y;
+    m[14] = z;
+    m
+}
+
+fn rotation_x(angle: f32) -> Matrix {
+    let (s, c) = angle.sin_cos();
+    [
+        1.0, 0.0, 0.0, 0.0, //
+        0.0, c, s, 0.0, //
+        0.0, -s, c, 0.0, //
+        0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+fn rotation_y(angle: f32) -> Matrix {
+    let (s, c) = angle.sin_cos();
+    [
+        c, 0.0, -s, 0.0, //
+        0.0, 1.0, 0.0, 0.0, //
+        s, 0.0, c, 0.0, //
+        0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+fn perspective(fov: f32, aspect: f32, near: f32, far: f32) -> Matrix {
+    let f = 1.0 / (fov / 2.0).tan();
+    let depth = near - far;
+    [
+        f / aspect, 0.0, 0.0, 0.0, //
+        0.0, f, 0.0, 0.0, //
+        0.0, 0.0, (far + near) / depth, -1.0, //
+        0.0, 0.0, 2.0 * far * near / depth, 0.0,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(m: &Matrix, p: [f32; 3]) -> [f32; 3] {
+        let mut out = [0.0; 4];
+        for (row, value) in out.iter_mut().enumerate() {
+            *value = m[row] * p[0] + m[4 + row] * p[1] + m[8 + row] * p[2] + m[12 + row];
+        }
+        [out[0] / out[3], out[1] / out[3], out[2] / out[3]]
+    }
+
+    fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+        a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-5)
+    }
+
+    #[test]
+    fn matrices() {
+        let m = multiply(&translation(1.0, 2.0, 3.0), &scaling(2.0, 2.0, 2.0));
+        assert!(close(apply(&m, [1.0, 1.0, 1.0]), [3.0, 4.0, 5.0]));
+        assert_eq!(multiply(&m, &identity()), m);
+        // a quarter turn about y takes x to -z, and about x takes y to z
+        assert!(close(apply(&rotation_y(std::f32::consts::FRAC_PI_2), [1.0, 0.0, 0.0]), [0.0, 0.0, -1.0]));
+        assert!(close(apply(&rotation_x(std::f32::consts::FRAC_PI_2), [0.0, 1.0, 0.0]), [0.0, 0.0, 1.0]));
+        // the near and far planes end up at the edges of clip space
+        let p = perspective(1.0, 1.0, 1.0, 10.0);
+        assert!((apply(&p, [0.0, 0.0, -1.0])[2] + 1.0).abs() < 1e-5);
+        assert!((apply(&p, [0.0, 0.0, -10.0])[2] - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn volume_in_view() {
+        // every corner of the stack lands inside clip space
+        // whichever way it is turned
+        let (columns, rows, layers) = (20, 10, 30);
+        for &(yaw, pitch) in [(0.0, 0.0), (0.6, 0.4), (2.0, -1.5), (4.0, 1.5)].iter() {
+            let v = view(columns, rows, layers, 1.5, yaw, pitch);
+            for &layer in [0, layers - 1].iter() {
+                let m = multiply(&v, &translation(0.0, 0.0, layer_depth(layer, layers, columns, rows)));
+                for &(x, y) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)].iter() {
+                    let p = apply(&m, [x, y, 0.0]);
+                    assert!(p.iter().all(|c| c.abs() <= 1.0), "{:?}", p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fitted() {
+        assert_eq!(fit(10, 10, 200, 100), (0.5, 1.0));
+        assert_eq!(fit(20, 10, 100, 100), (1.0, 0.5));
+        assert_eq!(fit(0, 10, 100, 100), (1.0, 1.0));
+        assert!((layer_depth(0, 3, 10, 5) + 0.2).abs() < 1e-6);
+        assert!(layer_depth(1, 3, 10, 5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn transparent_background() {
+        let colours = vec![Colour::new(255, 255, 255, 1.0), Colour::new(255, 0, 0, 1.0)];
+        let cells = vec![vec![0, 1]];
+        assert_eq!(layer_pixels(&cells, &colours, false), vec![255, 255, 255, 255, 255, 0, 0, 255]);
+        assert_eq!(layer_pixels(&cells, &colours, true), vec![255, 255, 255, 0, 255, 0, 0, 255]);
+    }
+}
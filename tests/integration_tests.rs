@@ -1,5 +1,45 @@
 use aca;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn temp_file(name: &str) -> String {
+    // a name of its own for every file a test writes, so that
+    // test runs at the same time do not clash
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::SeqCst);
+    let file = std::env::temp_dir().join(format!("aca_{}_{}_{}", std::process::id(), count, name));
+    file.to_str().unwrap().to_string()
+}
+
+fn small_config() -> aca::experiment::Config {
+    // a quick 2D experiment for the tests to change as needed
+    aca::experiment::Config {
+        num_mevs: 5,
+        population_size: 5,
+        num_states: 3,
+        tournament_size: 4,
+        width: 15,
+        height: 15,
+        num_iters: 8,
+        ..aca::experiment::Config::default()
+    }
+}
+
+fn small_1d_config() -> aca::experiment::Config {
+    // a quick 1D experiment, whose rule strings have length 7
+    aca::experiment::Config {
+        population_size: 6,
+        is_2d: false,
+        width: 21,
+        height: 21,
+        num_iters: 1,
+        start_population: vec![1, 2, 1],
+        crossover: aca::mutation::CrossoverOperator::KPoint(aca::mutation::KPoint { max_cuts: 3 }),
+        max_mutations: 3,
+        ..small_config()
+    }
+}
+
 #[test]
 fn example_2D() {
 
@@ -45,13 +85,14 @@ fn example_2D() {
     let automata = aca::population::make_2d_population(
         population_size, num_states, neighbourhood_size
     );
+    let mut cache = aca::cache::FitnessCache::new(1000);
     let mut boards = aca::board::make_boards(
-        automata, width, height, num_iters, &start_population
+        automata, width, height, num_iters, &start_population, &mut cache
     );
 
     for i in 0..num_mevs {
         println!("mev: {}", i);
-        aca::tournament::tournament(tournament_size, &mut boards, max_cuts, max_mutations, &start_population, num_iters);
+        aca::tournament::tournament(tournament_size, &mut boards, max_cuts, max_mutations, &start_population, num_iters, aca::tournament::Replacement::Worst, &mut cache);
     }
 
     let results = aca::board::write_results(&boards, output_file);
@@ -90,6 +131,11 @@ fn make1d() {
         neighbourhood_size: 1,
         rule_string: vec![0, 1, 2, 2, 0, 0, 1],
         num_states: 3,
+        mutation_rate: 0.0,
+        id: 0,
+        parents: vec![],
+        cut_points: vec![],
+        mutated_positions: vec![],
     };
     let mut board = aca::board::Board::initialise(5, 3);
     board.fill(a1d, &[1, 2, 1]);
@@ -122,6 +168,11 @@ fn make1d_apoptotic() {
         neighbourhood_size: 1,
         rule_string: vec![0, 0, 0, 0, 1, 0, 0],
         num_states: 3,
+        mutation_rate: 0.0,
+        id: 0,
+        parents: vec![],
+        cut_points: vec![],
+        mutated_positions: vec![],
     };
     let mut board = aca::board::Board::initialise(5, 3);
     board.fill(a1d, &[1, 2, 1]);
@@ -149,6 +200,11 @@ fn make_2d() {
             1, 0, 2, 0, 1, 1, 2, 2, 0, 0, 0, 1, 2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0,
         ],
         num_states: 3,
+        mutation_rate: 0.0,
+        id: 0,
+        parents: vec![],
+        cut_points: vec![],
+        mutated_positions: vec![],
     };
     let mut board = aca::board::Board::initialise(7, 7);
     board.fill(a2d, &[1, 2, 0, 1]);
@@ -218,7 +274,20 @@ fn run_tournament() {
         board.next_board();
         boards.push(board);
     }
-    aca::tournament::tournament(4, &mut boards, 3, 3, &start_population, 1);
+    let mut cache = aca::cache::FitnessCache::new(0);
+    let event = aca::tournament::tournament(
+        4, &mut boards, 3, 3, &start_population, 1, aca::tournament::Replacement::Worst, &mut cache
+    );
+    // with the cache off both children are run
+    assert_eq!(cache.evaluated, 2);
+    assert_eq!(event.replaced.len(), 2);
+
+    // children that are no better than the worst boards are
+    // thrown away rather than overwriting them
+    let event = aca::tournament::tournament(
+        4, &mut boards, 3, 3, &start_population, 1, aca::tournament::Replacement::IfBetter, &mut cache
+    );
+    assert_eq!(event.replaced.len() + event.rejected, 2);
 }
 
 #[test]
@@ -294,3 +363,707 @@ fn see_result() {
     let result = b.result();
     println!("{}", result);
 }
+
+#[test]
+fn live_counts_per_generation() {
+    let a1d = aca::automata::Automata {
+        is_2d: false,
+        neighbourhood_size: 1,
+        rule_string: vec![0, 0, 0, 0, 1, 0, 0],
+        num_states: 3,
+        mutation_rate: 0.0,
+        id: 0,
+        parents: vec![],
+        cut_points: vec![],
+        mutated_positions: vec![],
+    };
+    let mut board = aca::board::Board::initialise(5, 3);
+    board.fill(a1d, &[1, 2, 1]);
+    board.next_board();
+    assert_eq!(board.live_counts, vec![3, 1, 0]);
+    assert_eq!(aca::novelty::behaviour(&board), vec![3.0, 1.0, 0.0]);
+    // working out the rows again keeps one count per row
+    board.next_board();
+    assert_eq!(board.live_counts, vec![3, 1, 0]);
+    board.empty();
+    assert!(board.live_counts.is_empty());
+}
+
+#[test]
+fn run_novelty_tournament() {
+    let start_population = [0, 1, 0, 1, 2, 1, 0, 1, 0];
+    let automata = aca::population::make_2d_population(8, 3, 1);
+    let mut cache = aca::cache::FitnessCache::new(100);
+    let mut boards = aca::board::make_boards(automata, 15, 15, 10, &start_population, &mut cache);
+    let mut archive = aca::novelty::Archive::new(3, 0.0, 5);
+    for objective in &[
+        aca::novelty::Objective::Fitness,
+        aca::novelty::Objective::Novelty,
+        aca::novelty::Objective::Blend(0.5),
+    ] {
+        aca::tournament::novelty_tournament(
+            4,
+            &mut boards,
+            3,
+            3,
+            &start_population,
+            10,
+            aca::tournament::Replacement::Worst,
+            &mut archive,
+            *objective,
+            &mut cache,
+        );
+    }
+    assert_eq!(boards.len(), 8);
+    assert!(archive.behaviours.len() <= 5);
+}
+
+#[test]
+fn novelty_changes_selection() {
+    // boards 0 and 1 are the fittest but behave the same, while
+    // boards 2 and 3 are the least fit but behave differently
+    let fitness = [10, 9, 1, 0];
+    let behaviours = [vec![5, 5], vec![5, 5], vec![0, 0], vec![50, 50]];
+    let population: Vec<aca::board::Board> = fitness
+        .iter()
+        .zip(behaviours.iter())
+        .map(|(f, live_counts)| {
+            let a1d = aca::automata::Automata::new1d(3, 1);
+            let mut board = aca::board::evaluate(a1d, 7, 3, &[1, 2, 1], 1);
+            board.fitness = *f;
+            board.live_counts = live_counts.clone();
+            board
+        })
+        .collect();
+    let ids: Vec<usize> = population.iter().map(|b| b.automata.as_ref().unwrap().id).collect();
+    for (objective, parents, replaced) in &[
+        (aca::novelty::Objective::Fitness, [ids[0], ids[1]], [2, 3]),
+        (aca::novelty::Objective::Novelty, [ids[3], ids[2]], [0, 1]),
+    ] {
+        let mut boards = population.clone();
+        let mut archive = aca::novelty::Archive::new(1, 0.0, 10);
+        // the sample is the whole population, so the best two
+        // breed and the worst two are replaced
+        let mut event = aca::tournament::novelty_tournament(
+            4,
+            &mut boards,
+            0,
+            0,
+            &[1, 2, 1],
+            1,
+            aca::tournament::Replacement::Worst,
+            &mut archive,
+            *objective,
+            &mut aca::cache::FitnessCache::new(0),
+        );
+        event.replaced.sort();
+        assert_eq!(event.replaced, replaced.to_vec());
+        for i in replaced {
+            assert_eq!(boards[*i].automata.as_ref().unwrap().parents, parents.to_vec());
+        }
+    }
+}
+
+#[test]
+fn run_generation() {
+    let start_population = [1, 2, 1];
+    let automata = aca::population::make_1d_population(9, 3, 1);
+    let mut boards = aca::board::make_boards(
+        automata, 21, 21, 1, &start_population, &mut aca::cache::FitnessCache::new(0)
+    );
+    let best_fitness = boards.iter().map(|b| b.fitness).max().unwrap();
+    let replaced = aca::generational::generation(4, &mut boards, 2, 3, 3, &start_population, 1);
+    assert_eq!(boards.len(), 9);
+    assert_eq!(replaced.len(), 7);
+    // the elite boards are kept, so the best fitness cannot drop
+    assert!(best_fitness <= boards.iter().map(|b| b.fitness).max().unwrap());
+}
+
+#[test]
+fn compare_schemes() {
+    let config = aca::experiment::Config {
+        population_size: 8,
+        width: 21,
+        height: 21,
+        num_iters: 10,
+        ..small_config()
+    };
+    let mut steady_state = aca::experiment::Experiment::new(config.clone());
+    steady_state.run();
+    assert_eq!(steady_state.mev, 5);
+    // each board is either run or copied from the cache
+    assert_eq!(steady_state.evaluations + steady_state.cache.hits, 8 + 5 * 2);
+
+    let mut generational = aca::experiment::Experiment::new(aca::experiment::Config {
+        scheme: aca::experiment::Scheme::Generational { elitism: 1 },
+        ..config
+    });
+    let initial_best = generational.best().fitness;
+    generational.run();
+    assert_eq!(generational.evaluations + generational.cache.hits, 8 + 5 * 7);
+    assert!(initial_best <= generational.best().fitness);
+    assert!(steady_state.mean_fitness() <= steady_state.best().fitness as f64);
+    assert!(generational.mean_fitness() <= generational.best().fitness as f64);
+}
+
+#[test]
+fn experiment_selection_operators() {
+    // only the first three boards are fit, so these operators
+    // never breed from the others (the truncated top third is
+    // widened by one once the first parent is taken out, and
+    // None is a tournament of the whole population, which
+    // picks the best two)
+    for selection in &[
+        Some(aca::selection::Selection::Roulette),
+        Some(aca::selection::Selection::Truncation(0.3)),
+        Some(aca::selection::Selection::Boltzmann(0.001)),
+        None,
+    ] {
+        for scheme in &[
+            aca::experiment::Scheme::SteadyState,
+            aca::experiment::Scheme::Generational { elitism: 2 },
+        ] {
+            let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+                tournament_size: 6,
+                scheme: *scheme,
+                selection: *selection,
+                ..small_1d_config()
+            });
+            for (i, board) in experiment.boards.iter_mut().enumerate() {
+                board.fitness = [10, 9, 8, 0, 0, 0][i];
+            }
+            let ids: Vec<usize> = experiment.boards.iter().map(|b| b.automata.as_ref().unwrap().id).collect();
+            let parents = if selection.is_none() { &ids[..2] } else { &ids[..3] };
+            experiment.step();
+            let children: Vec<&aca::automata::Automata> = experiment
+                .boards
+                .iter()
+                .map(|b| b.automata.as_ref().unwrap())
+                .filter(|a| !a.parents.is_empty())
+                .collect();
+            assert!(!children.is_empty());
+            for child in children {
+                assert!(child.parents.iter().all(|id| parents.contains(id)), "{:?} {:?}", selection, scheme);
+            }
+        }
+    }
+}
+
+#[test]
+fn experiment_replacement_strategies() {
+    for replacement in &[
+        aca::tournament::Replacement::Worst,
+        aca::tournament::Replacement::IfBetter,
+        aca::tournament::Replacement::Parents,
+        aca::tournament::Replacement::Random,
+        aca::tournament::Replacement::Crowding,
+    ] {
+        let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+            population_size: 6,
+            replacement: *replacement,
+            ..small_config()
+        });
+        let worst_fitness = experiment.boards.iter().map(|b| b.fitness).min().unwrap();
+        experiment.run();
+        let statistics = experiment.statistics();
+        assert_eq!(statistics.replacement, *replacement);
+        assert_eq!(statistics.replaced + statistics.rejected, 10);
+        if *replacement == aca::tournament::Replacement::IfBetter {
+            // children only go in when they beat a board, so the
+            // worst fitness in the population cannot drop
+            assert!(worst_fitness <= experiment.boards.iter().map(|b| b.fitness).min().unwrap());
+        } else {
+            assert_eq!(statistics.rejected, 0);
+        }
+        assert!(statistics.to_string().contains(&format!("replacement: {:?}", replacement)));
+    }
+}
+
+#[test]
+fn island_migration() {
+    let config = aca::experiment::Config { num_mevs: 4, ..small_config() };
+    // the islands only differ in how much they mutate
+    let configs = vec![
+        aca::experiment::Config { max_mutations: 1, ..config.clone() },
+        aca::experiment::Config { max_mutations: 5, ..config.clone() },
+        aca::experiment::Config { max_mutations: 10, ..config },
+    ];
+    let mut islands = aca::island::Archipelago::new(configs, aca::island::Topology::Ring, 2, 1);
+    assert_eq!(islands.destinations(2), vec![0]);
+    islands.run();
+    assert_eq!(islands.mev, 4);
+    assert_eq!(islands.migrations, 2);
+    for island in &islands.islands {
+        assert_eq!(island.boards.len(), 5);
+        assert_eq!(island.mev, 4);
+    }
+
+    // the best board of an island turns up on all the others
+    islands.topology = aca::island::Topology::FullyConnected;
+    assert_eq!(islands.destinations(1), vec![0, 2]);
+    let best_rules = islands.islands[0].best().automata.as_ref().unwrap().rule_string.clone();
+    islands.num_migrants = 1;
+    islands.migrate();
+    for island in &islands.islands[1..] {
+        assert!(island
+            .boards
+            .iter()
+            .any(|b| b.automata.as_ref().unwrap().rule_string == best_rules));
+    }
+    let best_fitness = islands.islands.iter().map(|island| island.best().fitness).max().unwrap();
+    assert_eq!(islands.best().fitness, best_fitness);
+}
+
+#[test]
+fn experiment_crossover_operators() {
+    for operator in &[
+        aca::mutation::CrossoverOperator::KPoint(aca::mutation::KPoint { max_cuts: 2 }),
+        aca::mutation::CrossoverOperator::Uniform(aca::mutation::Uniform { swap_probability: 0.5 }),
+        aca::mutation::CrossoverOperator::Blockwise(aca::mutation::Blockwise { max_blocks: 2 }),
+    ] {
+        let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+            population_size: 4,
+            crossover: *operator,
+            max_mutations: 0,
+            ..small_1d_config()
+        });
+        // the tournament breeds the two fit boards, one with
+        // every rule 1 and the other with every rule 2
+        for (i, board) in experiment.boards.iter_mut().enumerate() {
+            board.fitness = [10, 9, 0, 0][i];
+        }
+        experiment.boards[0].automata.as_mut().unwrap().rule_string = vec![0, 1, 1, 1, 1, 1, 1];
+        experiment.boards[1].automata.as_mut().unwrap().rule_string = vec![0, 2, 2, 2, 2, 2, 2];
+        experiment.step();
+        for i in 2..4 {
+            let child = experiment.boards[i].automata.as_ref().unwrap();
+            // the rules switch parent at every cut point and
+            // nowhere else (the first rule is locked)
+            let mut rule = 1;
+            let mut expected = Vec::new();
+            for j in 0..7 {
+                if child.cut_points.contains(&j) {
+                    rule = 3 - rule;
+                }
+                expected.push(rule);
+            }
+            let other: Vec<usize> = expected.iter().map(|r| 3 - r).collect();
+            assert!(child.rule_string[1..] == expected[1..] || child.rule_string[1..] == other[1..], "{:?}", child);
+            match operator {
+                aca::mutation::CrossoverOperator::KPoint(_) => {
+                    assert!(child.cut_points.len() <= 2);
+                    assert!(!child.cut_points.contains(&0));
+                }
+                aca::mutation::CrossoverOperator::Blockwise(_) => assert!(child.cut_points.len() <= 4),
+                aca::mutation::CrossoverOperator::Uniform(_) => (),
+            }
+        }
+    }
+}
+
+#[test]
+fn experiment_keeps_locked_rules() {
+    let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+        num_mevs: 20,
+        max_mutations: 7,
+        locked_positions: vec![0, 6],
+        crossover: aca::mutation::CrossoverOperator::Uniform(aca::mutation::Uniform { swap_probability: 0.5 }),
+        ..small_1d_config()
+    });
+    let last_rules: Vec<usize> = experiment
+        .boards
+        .iter()
+        .map(|b| b.automata.as_ref().unwrap().rule_string[6])
+        .collect();
+    experiment.run();
+    for board in &experiment.boards {
+        let automata = board.automata.as_ref().unwrap();
+        assert_eq!(automata.validate(), Ok(()));
+        assert!(last_rules.contains(&automata.rule_string[6]));
+    }
+}
+
+#[test]
+fn experiment_mutation_schedules() {
+    for schedule in &[
+        aca::adaptation::MutationSchedule::OneFifth { period: 4, factor: 1.5 },
+        aca::adaptation::MutationSchedule::ExponentialDecay { half_life: 2.0 },
+        aca::adaptation::MutationSchedule::LinearDecay { steps: 4 },
+        aca::adaptation::MutationSchedule::SelfAdaptive { learning_rate: 0.3 },
+    ] {
+        for scheme in &[
+            aca::experiment::Scheme::SteadyState,
+            aca::experiment::Scheme::Generational { elitism: 1 },
+        ] {
+            let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+                num_mevs: 6,
+                max_mutations: 6,
+                mutation_schedule: *schedule,
+                scheme: *scheme,
+                ..small_config()
+            });
+            experiment.run();
+            assert_eq!(experiment.mutation.steps, 6);
+            for board in &experiment.boards {
+                let automata = board.automata.as_ref().unwrap();
+                assert!(0.0 < automata.mutation_rate && automata.mutation_rate <= 0.5);
+            }
+        }
+    }
+}
+
+#[test]
+fn experiment_mutation_operators() {
+    let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+        num_states: 4,
+        max_mutations: 4,
+        mutation_operators: vec![
+            aca::mutation::MutationOperator::Point,
+            aca::mutation::MutationOperator::Creep { wrap: true },
+            aca::mutation::MutationOperator::Swap,
+            aca::mutation::MutationOperator::Inversion,
+        ],
+        ..small_config()
+    });
+    experiment.run();
+    for board in &experiment.boards {
+        assert_eq!(board.automata.as_ref().unwrap().validate(), Ok(()));
+    }
+}
+
+fn two_rule_strings() -> aca::experiment::Config {
+    // with two states and no neighbours there are only two
+    // possible rule strings (the first rule is always 0)
+    aca::experiment::Config {
+        num_mevs: 10,
+        population_size: 4,
+        num_states: 2,
+        neighbourhood_size: 0,
+        width: 11,
+        height: 11,
+        start_population: vec![1],
+        crossover: aca::mutation::CrossoverOperator::KPoint(aca::mutation::KPoint { max_cuts: 1 }),
+        max_mutations: 1,
+        ..small_1d_config()
+    }
+}
+
+#[test]
+fn experiment_duplicate_policies() {
+    for policy in &[
+        aca::diversity::DuplicatePolicy::Reject,
+        aca::diversity::DuplicatePolicy::Remutate { attempts: 3 },
+    ] {
+        // with two states and no neighbours there are only two
+        // possible rule strings, so duplicates are everywhere
+        let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+            duplicates: *policy,
+            ..two_rule_strings()
+        });
+        let initial = experiment.evaluations + experiment.cache.hits;
+        experiment.run();
+        assert!(0 < experiment.duplicates);
+        // every other child is either run or copied from the cache
+        let children = experiment.evaluations + experiment.cache.hits - initial;
+        assert_eq!(children + experiment.duplicates, 20);
+        assert!(experiment.diversity.unique_rules <= 2);
+    }
+}
+
+#[test]
+fn experiment_fitness_cache() {
+    // only two possible rule strings, so most children
+    // have been seen before
+    let config = aca::experiment::Config { cache_size: 10, ..two_rule_strings() };
+    let mut experiment = aca::experiment::Experiment::new(config.clone());
+    experiment.run();
+    assert!(0 < experiment.cache.hits);
+    assert!(experiment.cache.len() <= 2);
+    assert_eq!(experiment.cache.hits + experiment.cache.misses, 4 + 20);
+    // only the misses count as evaluations
+    assert_eq!(experiment.evaluations, experiment.cache.misses);
+    assert!(experiment.statistics().to_string().contains("%)"));
+
+    let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+        cache_size: 0,
+        ..config
+    });
+    experiment.run();
+    assert_eq!((experiment.cache.hits, experiment.cache.misses), (0, 0));
+    assert_eq!(experiment.evaluations, 4 + 20);
+    assert!(experiment.statistics().to_string().contains("cache hits: disabled"));
+}
+
+#[test]
+fn experiment_statistics_log() {
+    let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config { num_mevs: 4, ..small_config() });
+    experiment.run();
+    assert_eq!(experiment.history.len(), 4);
+    for (i, event) in experiment.history.iter().enumerate() {
+        assert_eq!(event.mev, i + 1);
+        assert_eq!(event.replaced.len(), 2);
+        assert!(event.worst_fitness as f64 <= event.median_fitness);
+        assert!(event.median_fitness <= event.best_fitness as f64);
+    }
+
+    let output_file = &temp_file("statistics_test.csv");
+    experiment.write_statistics(output_file, aca::statistics::Format::Csv).unwrap();
+    let contents = std::fs::read_to_string(output_file).unwrap();
+    assert_eq!(contents.lines().count(), 5);
+    experiment.write_statistics(output_file, aca::statistics::Format::JsonLines).unwrap();
+    let contents = std::fs::read_to_string(output_file).unwrap();
+    assert_eq!(contents.lines().count(), 4);
+    assert!(contents.starts_with("{\"mev\":1,"));
+    std::fs::remove_file(output_file).unwrap();
+}
+
+#[test]
+fn experiment_hall_of_fame() {
+    let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+        num_mevs: 10,
+        population_size: 6,
+        hall_of_fame_size: 3,
+        ..small_config()
+    });
+    experiment.run();
+    let entries = &experiment.hall_of_fame.entries;
+    assert!(entries.len() <= 3);
+    for (i, entry) in entries.iter().enumerate() {
+        assert!(entry.mev <= 10);
+        assert!(0 < entry.fitness);
+        // children know their parents, the initial population does not
+        assert_eq!(entry.automata.parents.is_empty(), entry.mev == 0);
+        for other in &entries[i + 1..] {
+            assert!(other.fitness <= entry.fitness);
+            assert_ne!(other.automata.rule_string, entry.automata.rule_string);
+        }
+    }
+
+    let population_file = &temp_file("population_test.txt");
+    let hall_of_fame_file = &temp_file("hall_of_fame_test.txt");
+    experiment.write_results(population_file, hall_of_fame_file).unwrap();
+    let contents = std::fs::read_to_string(population_file).unwrap();
+    assert_eq!(contents.lines().count(), 6);
+    let contents = std::fs::read_to_string(hall_of_fame_file).unwrap();
+    assert_eq!(contents.lines().count(), entries.len());
+    std::fs::remove_file(population_file).unwrap();
+    std::fs::remove_file(hall_of_fame_file).unwrap();
+}
+
+#[test]
+fn experiment_genealogy() {
+    let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+        num_mevs: 10,
+        population_size: 6,
+        ..small_config()
+    });
+    experiment.run();
+    // every board in the population can be traced back
+    // to the initial population
+    for board in &experiment.boards {
+        let automata = board.automata.as_ref().unwrap();
+        let lineage = experiment.genealogy.lineage(automata.id);
+        assert!(lineage.nodes.contains_key(&automata.id));
+        for node in lineage.nodes.values() {
+            assert_eq!(node.parents.is_empty(), node.mev == 0);
+            for parent in &node.parents {
+                assert!(lineage.nodes[parent].mev < node.mev);
+            }
+        }
+    }
+
+    let output_file = &temp_file("genealogy_test.dot");
+    experiment.write_genealogy(output_file, aca::lineage::GraphFormat::Dot).unwrap();
+    let contents = std::fs::read_to_string(output_file).unwrap();
+    assert!(contents.starts_with("digraph genealogy {"));
+    std::fs::remove_file(output_file).unwrap();
+}
+
+#[test]
+fn experiment_early_stopping() {
+    use aca::experiment::{Config, Experiment, StopReason};
+    let config = small_config();
+
+    let mut experiment = Experiment::new(config.clone());
+    experiment.run();
+    assert_eq!(experiment.mev, 5);
+    assert_eq!(experiment.stop_reason, Some(StopReason::MaxMevs));
+
+    let mut experiment = Experiment::new(Config { target_fitness: Some(0), ..config.clone() });
+    experiment.run();
+    assert_eq!(experiment.mev, 0);
+    assert_eq!(experiment.statistics().stop_reason, Some(StopReason::TargetFitness));
+
+    let mut experiment = Experiment::new(Config { num_mevs: 1000, patience: Some(3), ..config.clone() });
+    experiment.run();
+    assert_eq!(experiment.stop_reason, Some(StopReason::NoImprovement));
+    assert_eq!(experiment.mev, experiment.last_improvement + 3);
+
+    let mut experiment = Experiment::new(Config { min_diversity: Some(f64::INFINITY), ..config.clone() });
+    experiment.run();
+    assert_eq!(experiment.stop_reason, Some(StopReason::DiversityCollapse));
+
+    let budget = std::time::Duration::from_secs(0);
+    let mut experiment = Experiment::new(Config { num_mevs: 1000, time_budget: Some(budget), ..config.clone() });
+    experiment.run();
+    assert_eq!(experiment.stop_reason, Some(StopReason::TimeBudget));
+    // the clock starts with the first step
+    assert_eq!(experiment.mev, 1);
+    assert!(experiment.statistics().to_string().ends_with("stopped: ran out of time"));
+
+    // time spent before running does not use up the budget
+    let budget = std::time::Duration::from_millis(200);
+    let mut experiment = Experiment::new(Config { time_budget: Some(budget), ..config });
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    experiment.step();
+    assert!(!experiment.finished());
+}
+
+#[test]
+fn space_time_png() {
+    use aca::colour::Colour;
+    let mut a1d = aca::automata::Automata::new1d(3, 1);
+    a1d.rule_string = vec![0, 1, 2, 2, 0, 0, 1];
+    let board = aca::board::evaluate(a1d, 5, 3, &[1, 2, 1], 1);
+    let colours = board.colours(&aca::palette::Palette::Greyscale);
+    assert_eq!(colours[0], aca::palette::BACKGROUND);
+    assert_eq!(colours[2], Colour::new(0, 0, 0, 1.0));
+    // one row of pixels per generation
+    let image = board.as_image(&colours, 4);
+    assert_eq!((image.width, image.height), (20, 12));
+
+    let output_file = &temp_file("space_time_test.png");
+    board.write_png(output_file, &colours, 4).unwrap();
+    let contents = std::fs::read(output_file).unwrap();
+    assert_eq!(&contents[1..4], b"PNG");
+    std::fs::remove_file(output_file).unwrap();
+}
+
+#[test]
+fn animated_2d_board() {
+    use aca::colour::Colour;
+    let mut a2d = aca::automata::Automata::new2d(3, 1);
+    a2d.rule_string = vec![0, 1, 2, 0, 2, 0, 1, 1, 0, 1, 2, 1, 0, 2, 0, 0, 1, 0, 0];
+    let mut board = aca::board::Board::initialise(7, 7);
+    let layers = board.run_recorded(a2d.clone(), &[1, 2, 1, 2, 0, 2, 1, 2, 1], 4);
+    // the start layer and then one layer per generation run
+    assert_eq!(layers.len(), board.live_counts.len());
+    assert_eq!(layers.last().unwrap(), &board.cell_states);
+    let expected = aca::board::evaluate(a2d, 7, 7, &[1, 2, 1, 2, 0, 2, 1, 2, 1], 4);
+    assert_eq!(board.fitness, expected.fitness);
+    // replaying the finished board gives the same layers back
+    assert_eq!(expected.history(&[1, 2, 1, 2, 0, 2, 1, 2, 1], 4), layers);
+
+    let colours = vec![
+        Colour::new(0, 0, 0, 1.0),
+        Colour::new(255, 0, 0, 1.0),
+        Colour::new(0, 0, 255, 1.0),
+    ];
+    let animation = aca::image::Animation::from_layers(&layers, &colours, 3, 200);
+    let output_file = &temp_file("animation_test.png");
+    animation.write_apng(output_file).unwrap();
+    let contents = std::fs::read(output_file).unwrap();
+    assert!(contents.windows(4).any(|w| w == b"acTL"));
+    std::fs::remove_file(output_file).unwrap();
+}
+
+#[test]
+fn board_svg() {
+    let mut experiment = aca::experiment::Experiment::new(aca::experiment::Config {
+        num_mevs: 2,
+        population_size: 4,
+        ..small_config()
+    });
+    experiment.run();
+    let best = experiment.best();
+    let colours = best.colours(&aca::palette::Palette::Viridis);
+    let options = aca::svg::SvgOptions {
+        legend: true,
+        title: true,
+        ..aca::svg::SvgOptions::default()
+    };
+    let output_file = &temp_file("board_test.svg");
+    best.write_svg(output_file, &colours, &options).unwrap();
+    let contents = std::fs::read_to_string(output_file).unwrap();
+    assert!(contents.starts_with("<svg"));
+    assert!(contents.contains(&format!("fitness {}</text>", best.fitness)));
+    // never more rects than cells, plus the background and legend
+    assert!(contents.matches("<rect").count() <= 15 * 15 + 1 + 3);
+    std::fs::remove_file(output_file).unwrap();
+}
+
+#[test]
+fn experiment_report() {
+    let mut experiment = aca::experiment::Experiment::new(small_config());
+    experiment.run();
+    let output_file = &temp_file("report_test.html");
+    experiment.write_report(output_file, &aca::palette::Palette::Viridis, 3).unwrap();
+    let contents = std::fs::read_to_string(output_file).unwrap();
+    assert!(contents.starts_with("<!DOCTYPE html>"));
+    assert!(contents.ends_with("</html>\n"));
+    // two convergence plots and up to three automata
+    assert_eq!(contents.matches("<polyline").count(), 5);
+    let num_automata = contents.matches("class=\"automata\"").count();
+    assert!((1..=3).contains(&num_automata));
+    std::fs::remove_file(output_file).unwrap();
+}
+
+#[test]
+fn config_validation() {
+    use aca::experiment::Config;
+    assert_eq!(Config::default().validate(), Ok(()));
+    assert_eq!(Config::default().rule_string_length(), 82);
+    let config = Config { num_states: 2, ..Config::default() };
+    assert!(config.validate().is_err());
+    // a tournament has to be the tournament size
+    let config = Config { selection: Some(aca::selection::Selection::Tournament(3)), ..Config::default() };
+    assert!(config.validate().is_err());
+    assert_eq!(Config::default().parent_selection(), aca::selection::Selection::Tournament(7));
+    let mut config = Config {
+        is_2d: false,
+        num_states: 3,
+        start_population: vec![1, 2, 1],
+        ..Config::default()
+    };
+    // 15 mutations do not fit in a rule string of length 7, and
+    // a 1D board is not made in 50 iterations
+    assert!(config.validate().is_err());
+    config.fit_defaults(None, None, None);
+    assert_eq!(config.max_mutations, 7);
+    assert_eq!(config.num_iters, 1);
+    assert_eq!(config.validate(), Ok(()));
+    // numbers the user gave are not cut down to fit
+    let mut given = config.clone();
+    given.fit_defaults(None, None, Some(500));
+    assert_eq!(given.max_mutations, 500);
+    assert!(given.validate().is_err());
+    let mut given = config.clone();
+    given.fit_defaults(None, Some(7), None);
+    assert!(given.validate().is_err());
+    let mut given = config.clone();
+    given.fit_defaults(Some(50), None, None);
+    assert!(given.validate().is_err());
+    // every board needs at least one iteration
+    assert!(Config { num_iters: 0, ..config.clone() }.validate().is_err());
+    assert!(Config { num_iters: 0, ..Config::default() }.validate().is_err());
+
+    // settings that operators would otherwise panic on
+    use aca::selection::Selection;
+    for selection in &[
+        Selection::Truncation(0.0),
+        Selection::Truncation(1.5),
+        Selection::Boltzmann(0.0),
+        Selection::Boltzmann(f64::NAN),
+    ] {
+        assert!(Config { selection: Some(*selection), ..Config::default() }.validate().is_err());
+    }
+    let crossover = aca::mutation::CrossoverOperator::Uniform(aca::mutation::Uniform { swap_probability: 2.0 });
+    assert!(Config { crossover, ..Config::default() }.validate().is_err());
+    let mutation_schedule = aca::adaptation::MutationSchedule::OneFifth { period: 5, factor: 0.5 };
+    assert!(Config { mutation_schedule, ..Config::default() }.validate().is_err());
+    let objective = aca::novelty::Objective::Blend(1.5);
+    assert!(Config { objective, ..Config::default() }.validate().is_err());
+    assert!(Config { novelty_neighbours: 0, ..Config::default() }.validate().is_err());
+    let scheme = aca::experiment::Scheme::Generational { elitism: 11 };
+    assert!(Config { scheme, ..Config::default() }.validate().is_err());
+}